@@ -0,0 +1,222 @@
+// Deterministic benchmarking harness for the orchestration planning/
+// execution path. Replays a JSON workload file of scenarios against a mock
+// goblin backend - no real goblin-runtime child process, no network calls -
+// so latency and cost numbers stay comparable across commits and machines
+// instead of drifting with whatever provider happens to answer a real call.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::cost_estimator;
+use crate::error::GoblinResult;
+use crate::orchestration_error::{OrchestrationError, RetryPolicy};
+use crate::scheduler;
+
+fn default_iterations() -> usize {
+    1
+}
+fn default_mock_latency_ms() -> u64 {
+    50
+}
+fn default_mock_cost_per_token() -> f64 {
+    0.000002
+}
+
+/// One scenario to replay: an orchestration plan's text plus the mock
+/// backend's simulated per-step latency/cost and how many times to run it.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorkloadScenario {
+    pub name: String,
+    pub text: String,
+    #[serde(default)]
+    pub default_goblin: Option<String>,
+    #[serde(default = "default_iterations")]
+    pub iterations: usize,
+    #[serde(default = "default_mock_latency_ms")]
+    pub mock_latency_ms: u64,
+    #[serde(default = "default_mock_cost_per_token")]
+    pub mock_cost_per_token: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Workload {
+    pub scenarios: Vec<WorkloadScenario>,
+}
+
+/// min/median/p95/max over a latency sample, in milliseconds.
+#[derive(Serialize, Clone, Debug)]
+pub struct LatencyPercentiles {
+    pub min_ms: u64,
+    pub median_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+fn percentiles(mut samples: Vec<u64>) -> LatencyPercentiles {
+    if samples.is_empty() {
+        return LatencyPercentiles { min_ms: 0, median_ms: 0, p95_ms: 0, max_ms: 0 };
+    }
+    samples.sort_unstable();
+    let at = |p: f64| -> u64 {
+        let idx = (((samples.len() - 1) as f64) * p).round() as usize;
+        samples[idx]
+    };
+    LatencyPercentiles {
+        min_ms: samples[0],
+        median_ms: at(0.5),
+        p95_ms: at(0.95),
+        max_ms: *samples.last().unwrap(),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub iterations: usize,
+    pub plan_latency: LatencyPercentiles,
+    pub step_latency: LatencyPercentiles,
+    pub total_cost: f64,
+    pub estimated_tokens: usize,
+    pub actual_tokens: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BenchmarkReport {
+    pub scenarios: Vec<ScenarioResult>,
+    pub generated_at: u64,
+}
+
+/// Run `scenario.iterations` times against the mock backend, re-parsing the
+/// plan text fresh each iteration (so parse cost is captured too) and
+/// executing it through the real `scheduler::run`, but with an execute
+/// closure that sleeps `mock_latency_ms` and fabricates a result instead of
+/// calling `execute_task_impl` - this is the one substitution that makes the
+/// whole planning/scheduling path reproducible without a live goblin-runtime
+/// child or network access.
+async fn run_scenario(scenario: &WorkloadScenario) -> Result<ScenarioResult, String> {
+    let default_goblin = scenario.default_goblin.clone().unwrap_or_else(|| "websmith".to_string());
+    let iterations = scenario.iterations.max(1);
+
+    let mut plan_latencies = Vec::with_capacity(iterations);
+    let mut step_latencies = Vec::new();
+    let mut total_cost = 0.0f64;
+    let mut estimated_tokens = 0usize;
+    let mut actual_tokens = 0usize;
+
+    for _ in 0..iterations {
+        let plan_started = Instant::now();
+        let specs = scheduler::parse_plan(&scenario.text, &default_goblin)?;
+
+        let mock_latency_ms = scenario.mock_latency_ms;
+        let mock_cost_per_token = scenario.mock_cost_per_token;
+        let outcomes = scheduler::run(
+            specs.clone(),
+            move |spec, _dep_results| async move {
+                let step_started = Instant::now();
+                tokio::time::sleep(std::time::Duration::from_millis(mock_latency_ms)).await;
+
+                let estimated = cost_estimator::estimate_tokens_from_text(&spec.task);
+                // Real providers never return exactly what we estimated -
+                // jitter the "actual" token count +/-20% so the estimated-
+                // vs-actual comparison in the report means something. The
+                // jitter comes from a hash of the task text, so the same
+                // workload reports the same numbers on every run.
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                std::hash::Hash::hash(&spec.task, &mut hasher);
+                let jitter = 0.8 + (std::hash::Hasher::finish(&hasher) % 400) as f64 / 1000.0;
+                let actual = ((estimated as f64) * jitter).round().max(1.0) as usize;
+
+                Ok::<_, OrchestrationError>(json!({
+                    "taskId": format!("mock_{}", spec.id),
+                    "elapsedMs": step_started.elapsed().as_millis() as u64,
+                    "estimatedTokens": estimated,
+                    "actualTokens": actual,
+                    "cost": actual as f64 * mock_cost_per_token,
+                }))
+            },
+            RetryPolicy::default(),
+            false,
+            4,
+            |_, _| {},
+            |_| {},
+        )
+        .await;
+
+        plan_latencies.push(plan_started.elapsed().as_millis() as u64);
+
+        for outcome in &outcomes {
+            if let Some(elapsed) = outcome.result.get("elapsedMs").and_then(|v| v.as_u64()) {
+                step_latencies.push(elapsed);
+            }
+            if let Some(tokens) = outcome.result.get("estimatedTokens").and_then(|v| v.as_u64()) {
+                estimated_tokens += tokens as usize;
+            }
+            if let Some(tokens) = outcome.result.get("actualTokens").and_then(|v| v.as_u64()) {
+                actual_tokens += tokens as usize;
+            }
+            if let Some(cost) = outcome.result.get("cost").and_then(|v| v.as_f64()) {
+                total_cost += cost;
+            }
+        }
+    }
+
+    Ok(ScenarioResult {
+        name: scenario.name.clone(),
+        iterations,
+        plan_latency: percentiles(plan_latencies),
+        step_latency: percentiles(step_latencies),
+        total_cost,
+        estimated_tokens,
+        actual_tokens,
+    })
+}
+
+/// Load a workload file and replay every scenario in it, returning the
+/// aggregate report. Doesn't touch the durable store or the real goblin
+/// runtime - safe to run repeatedly in CI.
+pub async fn run_workload_impl(workload_path: &str) -> GoblinResult<BenchmarkReport> {
+    let contents = tokio::fs::read_to_string(workload_path)
+        .await
+        .map_err(|e| format!("Failed to read workload file {}: {}", workload_path, e))?;
+    let workload: Workload = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse workload file {}: {}", workload_path, e))?;
+
+    let mut scenarios = Vec::with_capacity(workload.scenarios.len());
+    for scenario in &workload.scenarios {
+        scenarios.push(run_scenario(scenario).await?);
+    }
+
+    Ok(BenchmarkReport {
+        scenarios,
+        generated_at: chrono::Utc::now().timestamp_millis() as u64,
+    })
+}
+
+/// POST a finished report to a collection endpoint (e.g. a dashboard that
+/// tracks these numbers across commits). Best-effort: a failed POST doesn't
+/// invalidate the report the caller already has in hand.
+pub async fn post_results(url: &str, report: &BenchmarkReport) -> GoblinResult<()> {
+    let client = reqwest::Client::new();
+    client
+        .post(url)
+        .json(report)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to POST benchmark results to {}: {}", url, e))?
+        .error_for_status()
+        .map_err(|e| format!("Benchmark collection endpoint {} returned an error: {}", url, e))?;
+    Ok(())
+}
+
+/// Run a workload file and, if `post_url` is set, forward the resulting
+/// report to it. Returns the report either way so the caller (CLI, CI job,
+/// or Tauri command) can also print/save it locally.
+pub async fn run_and_report_impl(workload_path: &str, post_url: Option<String>) -> GoblinResult<BenchmarkReport> {
+    let report = run_workload_impl(workload_path).await?;
+    if let Some(url) = post_url {
+        post_results(&url, &report).await?;
+    }
+    Ok(report)
+}
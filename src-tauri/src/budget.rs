@@ -0,0 +1,33 @@
+// Per-task and session-wide spend limits for streaming task execution.
+//
+// `execute_task_impl` checks a task's own `max_cost_usd`/`max_tokens` (read
+// from its `args`) against the running totals accumulated as chunks stream
+// in, and separately checks the session-wide ceiling set via
+// `set_cost_budget`. Either one tripping aborts the stream early.
+
+use serde_json::Value as JsonValue;
+
+/// A task's own spend limits, parsed out of its `args` object.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TaskBudget {
+    pub max_cost_usd: Option<f64>,
+    pub max_tokens: Option<usize>,
+}
+
+impl TaskBudget {
+    pub fn from_args(args: Option<&JsonValue>) -> Self {
+        let max_cost_usd = args.and_then(|a| a.get("max_cost_usd")).and_then(|v| v.as_f64());
+        let max_tokens = args
+            .and_then(|a| a.get("max_tokens"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize);
+        TaskBudget { max_cost_usd, max_tokens }
+    }
+
+    /// Whether accumulating `cost_so_far`/`tokens_so_far` has crossed this
+    /// task's own limits (if any are set).
+    pub fn exceeded(&self, cost_so_far: f64, tokens_so_far: usize) -> bool {
+        self.max_cost_usd.map_or(false, |limit| cost_so_far > limit)
+            || self.max_tokens.map_or(false, |limit| tokens_so_far > limit)
+    }
+}
@@ -59,3 +59,78 @@ pub fn find_goblins_config() -> Result<PathBuf, String> {
 
     Err("goblins.yaml not found in current project; set GOBLINOS_CONFIG to a path to your project's goblins.yaml".to_string())
 }
+
+/// Read each registered goblin's `name`/`capability` pair out of
+/// `goblins.yaml`, for embedding-based routing (see `embeddings`).
+///
+/// This is intentionally a line scan rather than a real YAML parse: the
+/// file is small and hand-written, and the runtime (Node side) already owns
+/// full parsing of it. If the format grows real nesting or multi-line
+/// values this should become a `serde_yaml` deserialize instead.
+pub fn load_goblin_capabilities() -> Result<Vec<(String, String)>, String> {
+    let path = find_goblins_config()?;
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read goblins.yaml at {:?}: {}", path, e))?;
+
+    let mut goblins = Vec::new();
+    let mut current_name: Option<String> = None;
+    for raw_line in contents.lines() {
+        let line = raw_line.trim_start_matches('-').trim();
+        if let Some(rest) = line.strip_prefix("name:") {
+            current_name = Some(rest.trim().trim_matches('"').to_string());
+        } else if let Some(rest) = line.strip_prefix("capability:") {
+            if let Some(name) = current_name.take() {
+                goblins.push((name, rest.trim().trim_matches('"').to_string()));
+            }
+        }
+    }
+    Ok(goblins)
+}
+
+/// Read a named goblin's `tome:` block out of `goblins.yaml`: a multi-step,
+/// possibly-branching script in `tome`'s own grammar (see that module),
+/// letting that goblin run a whole sequenced program (see `run_tome_impl`)
+/// instead of a single task.
+///
+/// Same naive line-scan as `load_goblin_capabilities` rather than a real
+/// YAML parse - every non-blank line after `tome: |` up to the next
+/// goblin's `name:` is treated as part of the script and joined back into
+/// one line of tome text.
+pub fn load_goblin_tome(goblin_id: &str) -> Result<Option<String>, String> {
+    let path = find_goblins_config()?;
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read goblins.yaml at {:?}: {}", path, e))?;
+
+    let mut current_name: Option<String> = None;
+    let mut in_tome = false;
+    let mut tome_lines: Vec<String> = Vec::new();
+
+    for raw_line in contents.lines() {
+        let line = raw_line.trim_start_matches('-').trim();
+        if let Some(rest) = line.strip_prefix("name:") {
+            if in_tome && current_name.as_deref() == Some(goblin_id) {
+                break;
+            }
+            current_name = Some(rest.trim().trim_matches('"').to_string());
+            in_tome = false;
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("tome:") {
+            in_tome = current_name.as_deref() == Some(goblin_id);
+            let inline = rest.trim();
+            if in_tome && !inline.is_empty() && inline != "|" {
+                tome_lines.push(inline.to_string());
+            }
+            continue;
+        }
+        if in_tome && !raw_line.trim().is_empty() {
+            tome_lines.push(raw_line.trim().to_string());
+        }
+    }
+
+    if tome_lines.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(tome_lines.join(" ")))
+    }
+}
@@ -0,0 +1,59 @@
+// Background hot-reload for `cost_rates.json` and `goblins.yaml`. Both used
+// to be stuck with whatever they looked like at process start -
+// `cost_estimator::COST_RATES` via `lazy_static`, `embeddings`'s capability
+// vectors embedded once on first use - so picking up an edit meant
+// restarting the app. This polls each file's mtime on an interval and
+// reparses/invalidates on change, emitting `config://reloaded` so the
+// frontend can refresh anything it cached too.
+//
+// A plain mtime poll rather than a real filesystem-watcher crate, matching
+// this crate's preference for hand-rolled polling/parsing over a new
+// dependency for a small job (see `scheduler`'s and `tome`'s line parsers).
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use tauri::Emitter;
+
+use crate::config;
+use crate::cost_estimator;
+use crate::embeddings;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Start the background poller. Spawned once from `main.rs`'s `setup()`,
+/// alongside the runtime's other background tasks.
+pub fn spawn_config_watcher(app: tauri::AppHandle) {
+    tokio::spawn(async move {
+        let mut cost_rates_seen = mtime(Path::new(cost_estimator::COST_RATES_PATH));
+        let mut goblins_seen = config::find_goblins_config().ok().as_deref().and_then(mtime);
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let current = mtime(Path::new(cost_estimator::COST_RATES_PATH));
+            if current.is_some() && current != cost_rates_seen {
+                cost_rates_seen = current;
+                match cost_estimator::reload_cost_rates() {
+                    Ok(()) => {
+                        let _ = app.emit("config://reloaded", "cost_rates");
+                    }
+                    Err(e) => println!("Failed to reload cost_rates.json, keeping prior rates: {}", e),
+                }
+            }
+
+            if let Ok(goblins_path) = config::find_goblins_config() {
+                let current = mtime(&goblins_path);
+                if current.is_some() && current != goblins_seen {
+                    goblins_seen = current;
+                    embeddings::invalidate_goblin_capability_cache().await;
+                    let _ = app.emit("config://reloaded", "goblins");
+                }
+            }
+        }
+    });
+}
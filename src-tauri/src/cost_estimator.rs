@@ -4,6 +4,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::sync::RwLock;
 use lazy_static::lazy_static;
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -17,16 +18,34 @@ struct CostRatesConfig {
     providers: HashMap<String, ProviderConfig>,
 }
 
+/// Resolved relative to the Tauri app's working directory, same as the rest
+/// of this crate's config paths (see `config::find_goblins_config`).
+pub const COST_RATES_PATH: &str = "src-tauri/config/cost_rates.json";
+
+fn load_cost_rates_from_disk() -> CostRatesConfig {
+    match fs::read_to_string(COST_RATES_PATH) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|_| default_config()),
+        Err(_) => default_config(),
+    }
+}
+
 lazy_static! {
-    static ref COST_RATES: CostRatesConfig = {
-        let config_path = "src-tauri/config/cost_rates.json";
-        match fs::read_to_string(config_path) {
-            Ok(content) => {
-                serde_json::from_str(&content).unwrap_or_else(|_| default_config())
-            }
-            Err(_) => default_config(),
-        }
-    };
+    // `RwLock` rather than a bare value so `reload_cost_rates` can swap in a
+    // freshly-parsed config without restarting the app - `config_watch`
+    // calls it whenever `cost_rates.json`'s mtime changes.
+    static ref COST_RATES: RwLock<CostRatesConfig> = RwLock::new(load_cost_rates_from_disk());
+}
+
+/// Re-read and re-parse `cost_rates.json`, swapping it in only if it parses
+/// cleanly - a malformed edit leaves the previous rates in place rather than
+/// falling back to the hardcoded defaults.
+pub fn reload_cost_rates() -> Result<(), String> {
+    let content = fs::read_to_string(COST_RATES_PATH)
+        .map_err(|e| format!("Failed to read {}: {}", COST_RATES_PATH, e))?;
+    let parsed: CostRatesConfig = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", COST_RATES_PATH, e))?;
+    *COST_RATES.write().unwrap() = parsed;
+    Ok(())
 }
 
 fn default_config() -> CostRatesConfig {
@@ -53,7 +72,8 @@ fn default_config() -> CostRatesConfig {
 }
 
 pub fn cost_per_token(provider: &str, model: Option<&str>) -> f64 {
-    if let Some(provider_config) = COST_RATES.providers.get(provider) {
+    let rates = COST_RATES.read().unwrap();
+    if let Some(provider_config) = rates.providers.get(provider) {
         if let Some(model) = model {
             if let Some(models) = &provider_config.models {
                 if let Some(cost) = models.get(model) {
@@ -0,0 +1,120 @@
+// Per-plan spend guard consulted by `execute_orchestration_impl` before
+// admitting each step. Spend is tracked in micro-dollars (cost * 1_000_000,
+// rounded) so the running tally lives in a plain `AtomicU64` rather than a
+// mutex-guarded float - steps in the same readiness frontier are launched
+// as concurrent tasks (see `scheduler::run`), so admission has to be safe
+// to check and update from more than one task at once.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+const MICROS_PER_DOLLAR: f64 = 1_000_000.0;
+
+/// A step's admission was refused because it would have pushed the plan
+/// past its cost or token budget. Carried as the sentinel error from a
+/// step's execute closure so `execute_orchestration_impl` can tell a
+/// deferred step apart from one that actually ran and failed.
+pub const DEFERRED_SENTINEL: &str = "__budget_deferred__";
+
+pub struct CostTracker {
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    spent_micros: AtomicU64,
+    spent_tokens: AtomicUsize,
+    /// Serializes the admission-check-then-spend sequence in `try_reserve`.
+    /// The running tally itself stays in the plain atomics above for cheap
+    /// unsynchronized reads from `spent_usd`/`spent_tokens` - this lock is
+    /// only held for the duration of one `try_reserve` call, not every read.
+    reservation: std::sync::Mutex<()>,
+}
+
+impl CostTracker {
+    pub fn new(budget_usd: Option<f64>, budget_tokens: Option<usize>) -> Self {
+        CostTracker {
+            budget_usd,
+            budget_tokens,
+            spent_micros: AtomicU64::new(0),
+            spent_tokens: AtomicUsize::new(0),
+            reservation: std::sync::Mutex::new(()),
+        }
+    }
+
+    /// Whether admitting a step costing `step_cost_usd`/`step_tokens` would
+    /// cross whichever budget(s) are configured. `None` budgets never
+    /// refuse admission.
+    fn would_exceed(&self, step_cost_usd: f64, step_tokens: usize) -> bool {
+        if let Some(budget) = self.budget_usd {
+            if self.spent_usd() + step_cost_usd > budget {
+                return true;
+            }
+        }
+        if let Some(budget) = self.budget_tokens {
+            if self.spent_tokens.load(Ordering::Relaxed) + step_tokens > budget {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn record_spend(&self, cost_usd: f64, tokens: usize) {
+        self.spent_micros.fetch_add((cost_usd * MICROS_PER_DOLLAR).round() as u64, Ordering::Relaxed);
+        self.spent_tokens.fetch_add(tokens, Ordering::Relaxed);
+    }
+
+    /// Atomically check admission and record spend as a single step. Two
+    /// steps in the same readiness frontier are launched concurrently
+    /// (bounded by `max_concurrency`, see `scheduler::run`), so checking
+    /// `would_exceed` and then calling `record_spend` as two separate calls
+    /// let both steps pass admission before either one's spend landed,
+    /// overshooting the budget. Holding `reservation` across both closes
+    /// that race. Returns `true` if the step was admitted (and its spend is
+    /// now recorded), `false` if it would have exceeded the budget (nothing
+    /// was recorded).
+    pub fn try_reserve(&self, cost_usd: f64, tokens: usize) -> bool {
+        let _guard = self.reservation.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if self.would_exceed(cost_usd, tokens) {
+            return false;
+        }
+        self.record_spend(cost_usd, tokens);
+        true
+    }
+
+    pub fn spent_usd(&self) -> f64 {
+        self.spent_micros.load(Ordering::Relaxed) as f64 / MICROS_PER_DOLLAR
+    }
+
+    pub fn spent_tokens(&self) -> usize {
+        self.spent_tokens.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn try_reserve_refuses_once_budget_is_spent() {
+        let tracker = CostTracker::new(Some(1.0), None);
+        assert!(tracker.try_reserve(0.6, 0));
+        assert!(!tracker.try_reserve(0.6, 0));
+        assert!((tracker.spent_usd() - 0.6).abs() < f64::EPSILON);
+    }
+
+    /// Regression test for the would_exceed/record_spend race: many
+    /// concurrent reservations against a tight budget must never let total
+    /// spend exceed it, the way two non-atomic calls could.
+    #[test]
+    fn try_reserve_is_atomic_under_concurrent_admission() {
+        let tracker = Arc::new(CostTracker::new(Some(10.0), None));
+        let handles: Vec<_> = (0..100)
+            .map(|_| {
+                let tracker = tracker.clone();
+                std::thread::spawn(move || tracker.try_reserve(1.0, 0))
+            })
+            .collect();
+
+        let admitted = handles.into_iter().filter(|h| h.join().unwrap()).count();
+        assert_eq!(admitted, 10);
+        assert!(tracker.spent_usd() <= 10.0 + f64::EPSILON);
+    }
+}
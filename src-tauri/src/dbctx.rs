@@ -0,0 +1,497 @@
+// Embedded SQLite persistence for task history, cost, and orchestration
+// plans. `memory`/`RUNTIME_STATE` are process-local and vanish on restart;
+// this module is the durable counterpart they write through to. Statements
+// live in `sql` so the schema and query text stay in one place.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, Row};
+use tokio::sync::Mutex;
+
+use crate::sql;
+
+pub struct DbCtx {
+    conn: Connection,
+}
+
+// NOTE: this is intentionally a single `rusqlite::Connection` behind a
+// mutex, not a real connection pool (r2d2/deadpool-sqlite). Every accessor
+// below already serializes through this one lock, and embedded SQLite only
+// ever has one writer at a time regardless of how many connections a pool
+// would hand out, so a pool would just add a dependency (none of which
+// this crate otherwise pulls in - see `config_watch`'s mtime polling
+// instead of `notify`) without buying any real concurrency. If read-heavy
+// contention on this lock ever shows up in practice, that's the trigger to
+// revisit this with a real pool of read-only connections.
+lazy_static! {
+    static ref DB: Mutex<Option<DbCtx>> = Mutex::new(None);
+}
+
+impl DbCtx {
+    fn open(path: &PathBuf) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open database at {:?}: {}", path, e))?;
+        conn.execute_batch(sql::MIGRATIONS)
+            .map_err(|e| format!("Failed to run database migrations: {}", e))?;
+        for (table, column, ddl) in sql::PLAN_COLUMNS {
+            add_column_if_missing(&conn, table, column, ddl)?;
+        }
+        Ok(DbCtx { conn })
+    }
+}
+
+/// Run `ALTER TABLE <table> ADD COLUMN <column> <ddl>`, tolerating the
+/// "duplicate column name" error SQLite raises when it's already there -
+/// the closest thing to an idempotent `ADD COLUMN IF NOT EXISTS` without a
+/// real migration framework.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, ddl: &str) -> Result<(), String> {
+    match conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, ddl), []) {
+        Ok(_) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(_, Some(ref msg))) if msg.contains("duplicate column name") => Ok(()),
+        Err(e) => Err(format!("Failed to add column {}.{}: {}", table, column, e)),
+    }
+}
+
+/// Open (or create) the SQLite database at `path` and install it as the
+/// process-wide store. Call once during app setup, before any of the
+/// accessors below are used.
+pub async fn init(path: PathBuf) -> Result<(), String> {
+    let ctx = DbCtx::open(&path)?;
+    let mut db = DB.lock().await;
+    *db = Some(ctx);
+    Ok(())
+}
+
+pub async fn record_history_entry(goblin_id: &str, ts: u64, message: &str) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    ctx.conn
+        .execute(sql::INSERT_HISTORY, params![goblin_id, ts as i64, message])
+        .map_err(|e| format!("Failed to record history entry: {}", e))?;
+    Ok(())
+}
+
+pub async fn get_history_entries(goblin_id: &str, limit: Option<usize>) -> Result<Vec<(u64, String)>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    let limit = limit.unwrap_or(10) as i64;
+
+    let mut stmt = ctx
+        .conn
+        .prepare(sql::SELECT_HISTORY)
+        .map_err(|e| format!("Failed to prepare history query: {}", e))?;
+    let rows = stmt
+        .query_map(params![goblin_id, limit], |row| {
+            Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to query history: {}", e))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| format!("Failed to read history row: {}", e))?);
+    }
+    Ok(entries)
+}
+
+/// Enforce `memory::add_history_entry`'s retention policy for one goblin
+/// right after an insert: drop rows past `max_rows` (keeping the newest) and
+/// rows older than `max_age_ms` relative to `now`. Either bound may be
+/// `None` to leave that dimension unbounded.
+pub async fn enforce_history_retention(
+    goblin_id: &str,
+    max_rows: Option<usize>,
+    max_age_ms: Option<u64>,
+    now: u64,
+) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(max_rows) = max_rows {
+        ctx.conn
+            .execute(sql::DELETE_HISTORY_OVER_MAX_ROWS, params![goblin_id, max_rows as i64])
+            .map_err(|e| format!("Failed to enforce history row limit: {}", e))?;
+    }
+    if let Some(max_age_ms) = max_age_ms {
+        let cutoff = now.saturating_sub(max_age_ms) as i64;
+        ctx.conn
+            .execute(sql::DELETE_HISTORY_OLDER_THAN, params![goblin_id, cutoff])
+            .map_err(|e| format!("Failed to enforce history age limit: {}", e))?;
+    }
+    Ok(())
+}
+
+pub async fn upsert_goblin_last_seen(goblin_id: &str, ts: u64) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    ctx.conn
+        .execute(sql::UPSERT_GOBLIN_LAST_SEEN, params![goblin_id, ts as i64])
+        .map_err(|e| format!("Failed to record goblin last_seen: {}", e))?;
+    Ok(())
+}
+
+pub async fn get_goblin_last_seen(goblin_id: &str) -> Result<Option<u64>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    ctx.conn
+        .query_row(sql::SELECT_GOBLIN_LAST_SEEN, params![goblin_id], |row| {
+            row.get::<_, Option<i64>>(0)
+        })
+        .map(|v| v.map(|v| v as u64))
+        .or_else(|e| if e == rusqlite::Error::QueryReturnedNoRows { Ok(None) } else { Err(format!("Failed to read goblin last_seen: {}", e)) })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn record_task(
+    task_id: &str,
+    goblin: &str,
+    task: &str,
+    status: &str,
+    provider: Option<&str>,
+    model: Option<&str>,
+    total_cost: f64,
+    started_at: u64,
+    completed_at: Option<u64>,
+) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    ctx.conn
+        .execute(
+            sql::UPSERT_TASK,
+            params![
+                task_id,
+                goblin,
+                task,
+                status,
+                provider,
+                model,
+                total_cost,
+                started_at as i64,
+                completed_at.map(|v| v as i64),
+            ],
+        )
+        .map_err(|e| format!("Failed to record task: {}", e))?;
+    Ok(())
+}
+
+/// Sum `total_cost` per provider for tasks started within `[since, until]`.
+/// Either bound may be omitted to leave that side of the range open.
+pub async fn cost_by_provider(since: Option<u64>, until: Option<u64>) -> Result<HashMap<String, f64>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = ctx
+        .conn
+        .prepare(sql::SELECT_COST_BY_PROVIDER)
+        .map_err(|e| format!("Failed to prepare cost query: {}", e))?;
+    let rows = stmt
+        .query_map(
+            params![since.map(|v| v as i64), until.map(|v| v as i64)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+        )
+        .map_err(|e| format!("Failed to query cost by provider: {}", e))?;
+
+    let mut totals = HashMap::new();
+    for row in rows {
+        let (provider, total) = row.map_err(|e| format!("Failed to read cost row: {}", e))?;
+        totals.insert(provider, total);
+    }
+    Ok(totals)
+}
+
+/// Aggregate cost for `get_cost_summary`: the grand total plus per-provider
+/// and per-model breakdowns, optionally scoped to one goblin and/or a
+/// `[since, until]` window.
+pub async fn cost_summary(
+    goblin_id: Option<&str>,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<(f64, HashMap<String, f64>, HashMap<String, f64>), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    let since = since.map(|v| v as i64);
+    let until = until.map(|v| v as i64);
+
+    let total_cost: f64 = ctx
+        .conn
+        .query_row(sql::SELECT_COST_SUMMARY_TOTAL, params![goblin_id, since, until], |row| row.get(0))
+        .map_err(|e| format!("Failed to query total cost: {}", e))?;
+
+    let mut by_provider = HashMap::new();
+    {
+        let mut stmt = ctx
+            .conn
+            .prepare(sql::SELECT_COST_SUMMARY_BY_PROVIDER)
+            .map_err(|e| format!("Failed to prepare cost-by-provider query: {}", e))?;
+        let rows = stmt
+            .query_map(params![goblin_id, since, until], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+            .map_err(|e| format!("Failed to query cost by provider: {}", e))?;
+        for row in rows {
+            let (provider, total) = row.map_err(|e| format!("Failed to read cost-by-provider row: {}", e))?;
+            by_provider.insert(provider, total);
+        }
+    }
+
+    let mut by_model = HashMap::new();
+    {
+        let mut stmt = ctx
+            .conn
+            .prepare(sql::SELECT_COST_SUMMARY_BY_MODEL)
+            .map_err(|e| format!("Failed to prepare cost-by-model query: {}", e))?;
+        let rows = stmt
+            .query_map(params![goblin_id, since, until], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+            .map_err(|e| format!("Failed to query cost by model: {}", e))?;
+        for row in rows {
+            let (model, total) = row.map_err(|e| format!("Failed to read cost-by-model row: {}", e))?;
+            by_model.insert(model, total);
+        }
+    }
+
+    Ok((total_cost, by_provider, by_model))
+}
+
+/// A plan as loaded back from the store: the stable `OrchestrationPlanResult`
+/// plus the one piece of volatile-run context (`default_goblin`) needed to
+/// re-derive the same DAG via `scheduler::parse_plan` on resume.
+pub struct StoredPlan {
+    pub plan: crate::goblin_runtime::OrchestrationPlanResult,
+    pub default_goblin: String,
+}
+
+pub async fn save_orchestration_plan(
+    plan: &crate::goblin_runtime::OrchestrationPlanResult,
+    default_goblin: &str,
+) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+
+    ctx.conn
+        .execute(
+            sql::UPSERT_PLAN,
+            params![plan.id, plan.description, plan.status, plan.created_at as i64, default_goblin],
+        )
+        .map_err(|e| format!("Failed to record orchestration plan: {}", e))?;
+
+    for step in &plan.steps {
+        save_plan_step_locked(ctx, &plan.id, step)?;
+    }
+
+    Ok(())
+}
+
+fn save_plan_step_locked(ctx: &DbCtx, plan_id: &str, step: &crate::goblin_runtime::OrchestrationStepResult) -> Result<(), String> {
+    let result_json = step.result.as_ref().map(|v| v.to_string());
+    let depends_on_json = serde_json::to_string(&step.depends_on).map_err(|e| format!("Failed to serialize depends_on: {}", e))?;
+    ctx.conn
+        .execute(
+            sql::UPSERT_PLAN_STEP,
+            params![
+                step.id,
+                plan_id,
+                step.name,
+                step.goblin,
+                step.task,
+                depends_on_json,
+                step.state.as_str(),
+                step.started_at.map(|v| v as i64),
+                step.completed_at.map(|v| v as i64),
+                result_json,
+                step.attempts as i64,
+            ],
+        )
+        .map_err(|e| format!("Failed to record plan step: {}", e))?;
+    Ok(())
+}
+
+/// Write a single step's current state through to the store. Unlike
+/// `save_orchestration_plan`, this doesn't touch the parent plan row - used
+/// for incremental durability as steps complete mid-run, not just at the end.
+pub async fn save_plan_step(plan_id: &str, step: &crate::goblin_runtime::OrchestrationStepResult) -> Result<(), String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    save_plan_step_locked(ctx, plan_id, step)
+}
+
+fn step_state_from_str(s: &str) -> crate::scheduler::StepState {
+    use crate::scheduler::StepState;
+    match s {
+        "running" => StepState::Running,
+        "completed" => StepState::Completed,
+        "failed" => StepState::Failed,
+        "skipped" => StepState::Skipped,
+        "already_running" => StepState::AlreadyRunning,
+        "deferred" => StepState::Deferred,
+        _ => StepState::Pending,
+    }
+}
+
+fn row_to_plan_header(row: &Row) -> rusqlite::Result<(crate::goblin_runtime::OrchestrationPlanResult, String)> {
+    let plan = crate::goblin_runtime::OrchestrationPlanResult {
+        id: row.get(0)?,
+        description: row.get(1)?,
+        status: row.get(2)?,
+        created_at: row.get::<_, i64>(3)? as u64,
+        steps: Vec::new(),
+    };
+    let default_goblin: String = row.get(4)?;
+    Ok((plan, default_goblin))
+}
+
+fn load_plan_steps(ctx: &DbCtx, plan_id: &str) -> Result<Vec<crate::goblin_runtime::OrchestrationStepResult>, String> {
+    let mut stmt = ctx
+        .conn
+        .prepare(sql::SELECT_PLAN_STEPS)
+        .map_err(|e| format!("Failed to prepare plan steps query: {}", e))?;
+    let rows = stmt
+        .query_map(params![plan_id], |row| {
+            let depends_on_json: String = row.get(4)?;
+            let result_json: Option<String> = row.get(8)?;
+            let status: String = row.get(5)?;
+            Ok(crate::goblin_runtime::OrchestrationStepResult {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                goblin: row.get(2)?,
+                task: row.get(3)?,
+                depends_on: serde_json::from_str(&depends_on_json).unwrap_or_default(),
+                state: step_state_from_str(&status),
+                result: result_json.and_then(|s| serde_json::from_str(&s).ok()),
+                started_at: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+                completed_at: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                attempts: row.get::<_, i64>(9)? as u32,
+            })
+        })
+        .map_err(|e| format!("Failed to query plan steps: {}", e))?;
+
+    let mut steps = Vec::new();
+    for row in rows {
+        steps.push(row.map_err(|e| format!("Failed to read plan step row: {}", e))?);
+    }
+    Ok(steps)
+}
+
+pub async fn load_plan(plan_id: &str) -> Result<Option<StoredPlan>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+
+    let header = ctx
+        .conn
+        .query_row(sql::SELECT_PLAN, params![plan_id], row_to_plan_header);
+    let (mut plan, default_goblin) = match header {
+        Ok(h) => h,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(format!("Failed to load orchestration plan: {}", e)),
+    };
+
+    plan.steps = load_plan_steps(ctx, plan_id)?;
+    Ok(Some(StoredPlan { plan, default_goblin }))
+}
+
+pub async fn list_plans(limit: Option<usize>) -> Result<Vec<StoredPlan>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+    let limit = limit.unwrap_or(50) as i64;
+
+    let mut stmt = ctx
+        .conn
+        .prepare(sql::SELECT_PLANS)
+        .map_err(|e| format!("Failed to prepare plans query: {}", e))?;
+    let headers = stmt
+        .query_map(params![limit], row_to_plan_header)
+        .map_err(|e| format!("Failed to query orchestration plans: {}", e))?;
+
+    let mut plans = Vec::new();
+    for header in headers {
+        let (mut plan, default_goblin) = header.map_err(|e| format!("Failed to read plan row: {}", e))?;
+        plan.steps = load_plan_steps(ctx, &plan.id)?;
+        plans.push(StoredPlan { plan, default_goblin });
+    }
+    Ok(plans)
+}
+
+/// Plans not yet in a terminal state, oldest first - what `main.rs` checks
+/// for at startup so a crash mid-plan isn't silently lost.
+pub async fn list_incomplete_plans() -> Result<Vec<StoredPlan>, String> {
+    let db = DB.lock().await;
+    let ctx = db.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = ctx
+        .conn
+        .prepare(sql::SELECT_INCOMPLETE_PLANS)
+        .map_err(|e| format!("Failed to prepare incomplete plans query: {}", e))?;
+    let headers = stmt
+        .query_map([], row_to_plan_header)
+        .map_err(|e| format!("Failed to query incomplete plans: {}", e))?;
+
+    let mut plans = Vec::new();
+    for header in headers {
+        let (mut plan, default_goblin) = header.map_err(|e| format!("Failed to read plan row: {}", e))?;
+        plan.steps = load_plan_steps(ctx, &plan.id)?;
+        plans.push(StoredPlan { plan, default_goblin });
+    }
+    Ok(plans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goblin_runtime::{OrchestrationPlanResult, OrchestrationStepResult};
+    use crate::scheduler::StepState;
+
+    fn sample_step(id: &str) -> OrchestrationStepResult {
+        OrchestrationStepResult {
+            id: id.to_string(),
+            name: None,
+            goblin: "websmith".to_string(),
+            task: "do the thing".to_string(),
+            depends_on: Vec::new(),
+            state: StepState::Completed,
+            result: Some(serde_json::json!({ "ok": true })),
+            started_at: Some(1),
+            completed_at: Some(2),
+            attempts: 1,
+        }
+    }
+
+    /// Regression test for `plan_steps`' primary key: `scheduler::parse_plan`
+    /// numbers step ids per-parse starting from "step0", so two different
+    /// plans' first steps both land on id "step0" - they must not collide.
+    #[tokio::test]
+    async fn plan_steps_with_the_same_id_in_different_plans_dont_collide() {
+        let path = std::env::temp_dir().join(format!("goblin_dbctx_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        init(path.clone()).await.expect("init db");
+
+        let plan_a = OrchestrationPlanResult {
+            id: "plan_a".to_string(),
+            description: "plan a".to_string(),
+            steps: vec![sample_step("step0")],
+            created_at: 1,
+            status: "completed".to_string(),
+        };
+        let plan_b = OrchestrationPlanResult {
+            id: "plan_b".to_string(),
+            description: "plan b".to_string(),
+            steps: vec![sample_step("step0")],
+            created_at: 2,
+            status: "completed".to_string(),
+        };
+
+        save_orchestration_plan(&plan_a, "websmith").await.expect("save plan a");
+        save_orchestration_plan(&plan_b, "websmith").await.expect("save plan b");
+
+        let loaded_a = load_plan("plan_a").await.expect("load plan a").expect("plan a present");
+        let loaded_b = load_plan("plan_b").await.expect("load plan b").expect("plan b present");
+
+        assert_eq!(loaded_a.plan.steps.len(), 1);
+        assert_eq!(loaded_b.plan.steps.len(), 1);
+        assert_eq!(loaded_a.plan.steps[0].id, "step0");
+        assert_eq!(loaded_b.plan.steps[0].id, "step0");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,251 @@
+// Embedding-based automatic goblin routing.
+//
+// `parse_orchestration_impl`/`execute_orchestration_impl` call `route_task`
+// for any step the author didn't give an explicit `goblin:` prefix (see
+// `scheduler::StepSpec::auto_route`). Each registered goblin's capability
+// description is embedded once and cached as a unit vector; the bare task
+// text is embedded the same way and compared by dot product, which equals
+// cosine similarity once both sides are normalized. A best match below
+// `MIN_CONFIDENCE` is treated as no match at all, so a weak signal falls
+// back to `default_goblin` instead of misrouting the step.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use tokio::sync::Mutex;
+
+use crate::config;
+use crate::error::GoblinResult;
+
+/// Dimensionality used by the offline hashing provider. OpenAI/Ollama
+/// providers use whatever their model returns - routing only ever compares
+/// vectors produced by the same provider within a single call, so a mixed
+/// dimension across providers is never an issue.
+const OFFLINE_EMBEDDING_DIMENSION: usize = 256;
+
+/// Truncate task text to this many characters before embedding, so a very
+/// long task description doesn't blow past a model's context window.
+const MAX_EMBEDDING_INPUT_CHARS: usize = 2000;
+
+/// Minimum cosine similarity a goblin's capability vector must clear to be
+/// chosen over `default_goblin`.
+const MIN_CONFIDENCE: f32 = 0.2;
+
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed `text` into a fixed-dimension vector. Not assumed to be
+    /// normalized - callers normalize before comparing.
+    async fn embed(&self, text: &str) -> GoblinResult<Vec<f32>>;
+}
+
+/// OpenAI's `text-embedding-3-small` endpoint. Requires an API key stored
+/// via `store_api_key_secure("openai", ...)`.
+pub struct OpenAiEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    async fn embed(&self, text: &str) -> GoblinResult<Vec<f32>> {
+        let api_key = super::get_api_key_secure("openai")
+            .await?
+            .ok_or_else(|| "No OpenAI API key configured for embeddings".to_string())?;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post("https://api.openai.com/v1/embeddings")
+            .bearer_auth(api_key)
+            .json(&serde_json::json!({
+                "model": "text-embedding-3-small",
+                "input": text,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("OpenAI embeddings request failed: {}", e))?;
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse OpenAI embeddings response: {}", e))?;
+
+        let vector = body["data"][0]["embedding"]
+            .as_array()
+            .ok_or_else(|| "Unexpected OpenAI embeddings response shape".to_string())?
+            .iter()
+            .filter_map(|v| v.as_f64())
+            .map(|v| v as f32)
+            .collect();
+
+        Ok(vector)
+    }
+}
+
+/// A local Ollama embedding model (e.g. `nomic-embed-text`), matching this
+/// repo's Ollama-first philosophy for providers that don't need a paid key.
+pub struct OllamaEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    async fn embed(&self, text: &str) -> GoblinResult<Vec<f32>> {
+        let host = std::env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = std::env::var("OLLAMA_EMBEDDING_MODEL").unwrap_or_else(|_| "nomic-embed-text".to_string());
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("{}/api/embeddings", host))
+            .json(&serde_json::json!({ "model": model, "prompt": text }))
+            .send()
+            .await
+            .map_err(|e| format!("Ollama embeddings request failed: {}", e))?;
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Ollama embeddings response: {}", e))?;
+
+        let vector = body["embedding"]
+            .as_array()
+            .ok_or_else(|| "Unexpected Ollama embeddings response shape".to_string())?
+            .iter()
+            .filter_map(|v| v.as_f64())
+            .map(|v| v as f32)
+            .collect();
+
+        Ok(vector)
+    }
+}
+
+/// Deterministic, offline fallback: hashes whitespace-separated tokens into
+/// fixed buckets (a bag-of-words sketch). No network, no API key, never
+/// fails - used whenever no real provider is configured.
+pub struct OfflineHashingEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for OfflineHashingEmbeddingProvider {
+    async fn embed(&self, text: &str) -> GoblinResult<Vec<f32>> {
+        let mut vector = vec![0f32; OFFLINE_EMBEDDING_DIMENSION];
+        for token in text.split_whitespace() {
+            let mut hasher = DefaultHasher::new();
+            token.to_lowercase().hash(&mut hasher);
+            let bucket = (hasher.finish() as usize) % OFFLINE_EMBEDDING_DIMENSION;
+            vector[bucket] += 1.0;
+        }
+        Ok(vector)
+    }
+}
+
+/// Pick the embedding provider for this process. Defaults to the offline
+/// hashing fallback so routing works with zero setup; set
+/// `GOBLIN_EMBEDDING_PROVIDER=openai` or `=ollama` to use a real model.
+fn select_provider() -> Box<dyn EmbeddingProvider> {
+    match std::env::var("GOBLIN_EMBEDDING_PROVIDER").as_deref() {
+        Ok("openai") => Box::new(OpenAiEmbeddingProvider),
+        Ok("ollama") => Box::new(OllamaEmbeddingProvider),
+        _ => Box::new(OfflineHashingEmbeddingProvider),
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm: f32 = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in vector.iter_mut() {
+            *v /= norm;
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+lazy_static! {
+    /// Each registered goblin's unit-normalized capability vector, embedded
+    /// once on first use.
+    static ref GOBLIN_CAPABILITY_VECTORS: Mutex<Option<HashMap<String, Vec<f32>>>> = Mutex::new(None);
+    /// Task embeddings keyed by a hash of their (truncated) text, so the
+    /// same task string embedded twice doesn't pay for it twice.
+    static ref TASK_EMBEDDING_CACHE: Mutex<HashMap<u64, Vec<f32>>> = Mutex::new(HashMap::new());
+}
+
+async fn goblin_capability_vectors(provider: &dyn EmbeddingProvider) -> GoblinResult<HashMap<String, Vec<f32>>> {
+    {
+        let cache = GOBLIN_CAPABILITY_VECTORS.lock().await;
+        if let Some(vectors) = cache.as_ref() {
+            return Ok(vectors.clone());
+        }
+    }
+
+    let goblins = config::load_goblin_capabilities()?;
+    let mut vectors = HashMap::new();
+    for (name, capability) in goblins {
+        let mut vector = provider.embed(&capability).await?;
+        normalize(&mut vector);
+        vectors.insert(name, vector);
+    }
+
+    *GOBLIN_CAPABILITY_VECTORS.lock().await = Some(vectors.clone());
+    Ok(vectors)
+}
+
+/// Drop the cached capability vectors so the next routing call re-reads
+/// `goblins.yaml` and re-embeds from scratch, instead of serving the
+/// embedded-once-at-first-use cache forever. `config_watch` calls this
+/// whenever `goblins.yaml`'s mtime changes.
+pub async fn invalidate_goblin_capability_cache() {
+    *GOBLIN_CAPABILITY_VECTORS.lock().await = None;
+}
+
+async fn task_embedding(provider: &dyn EmbeddingProvider, text: &str) -> GoblinResult<Vec<f32>> {
+    let truncated: String = text.chars().take(MAX_EMBEDDING_INPUT_CHARS).collect();
+    let key = hash_text(&truncated);
+
+    {
+        let cache = TASK_EMBEDDING_CACHE.lock().await;
+        if let Some(vector) = cache.get(&key) {
+            return Ok(vector.clone());
+        }
+    }
+
+    let mut vector = provider.embed(&truncated).await?;
+    normalize(&mut vector);
+    TASK_EMBEDDING_CACHE.lock().await.insert(key, vector.clone());
+    Ok(vector)
+}
+
+/// Pick the best-matching goblin for `task` by cosine similarity against
+/// each registered goblin's capability vector, falling back to
+/// `default_goblin` when nothing clears `MIN_CONFIDENCE` (including when
+/// there's no goblins.yaml to read capabilities from, or embedding fails).
+pub async fn route_task(task: &str, default_goblin: &str) -> String {
+    let provider = select_provider();
+
+    let capability_vectors = match goblin_capability_vectors(provider.as_ref()).await {
+        Ok(vectors) if !vectors.is_empty() => vectors,
+        _ => return default_goblin.to_string(),
+    };
+
+    let task_vector = match task_embedding(provider.as_ref(), task).await {
+        Ok(vector) => vector,
+        Err(_) => return default_goblin.to_string(),
+    };
+
+    let mut best: Option<(&str, f32)> = None;
+    for (name, vector) in &capability_vectors {
+        let similarity = dot(&task_vector, vector);
+        if best.map_or(true, |(_, best_score)| similarity > best_score) {
+            best = Some((name.as_str(), similarity));
+        }
+    }
+
+    match best {
+        Some((name, score)) if score >= MIN_CONFIDENCE => name.to_string(),
+        _ => default_goblin.to_string(),
+    }
+}
@@ -0,0 +1,54 @@
+// Crate-wide error type for the runtime API. Replaces the ad hoc
+// `Result<_, String>` every function used to return, which lost structure
+// and forced the frontend into string-matching error messages to decide
+// what to show the user.
+//
+// `Io` and the generic `From<String>` impl below store a message rather
+// than the source error itself: `std::io::Error` isn't `Serialize`, and
+// this type has to cross the Tauri IPC boundary, so we capture its
+// `Display` output instead of the value.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "detail")]
+pub enum GoblinError {
+    #[error("runtime is not running")]
+    RuntimeNotRunning,
+
+    #[error("failed to spawn goblin runtime: {0}")]
+    Spawn(String),
+
+    #[error("io error: {0}")]
+    Io(String),
+
+    #[error("keyring error: {0}")]
+    Keyring(String),
+
+    #[error("runtime replied to request {id} with an error: {message}")]
+    Protocol { id: String, message: String },
+
+    #[error("timed out waiting for a response from the runtime")]
+    Timeout,
+
+    #[error("unexpected response shape: {0}")]
+    UnexpectedResponse(JsonValue),
+}
+
+impl From<std::io::Error> for GoblinError {
+    fn from(e: std::io::Error) -> Self {
+        GoblinError::Io(e.to_string())
+    }
+}
+
+/// Catch-all for subsystems (the SQLite store, config loading, ...) that
+/// still report failures as plain strings.
+impl From<String> for GoblinError {
+    fn from(message: String) -> Self {
+        GoblinError::Io(message)
+    }
+}
+
+pub type GoblinResult<T> = Result<T, GoblinError>;
@@ -1,68 +1,248 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 mod config;
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
-use tokio::sync::Mutex;
-use tokio::process::{Command, Child};
-use tokio::io::AsyncWriteExt;
+use tokio::sync::{oneshot, Mutex};
+use tokio::process::{Command, Child, ChildStdin};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use serde_json::{Value as JsonValue, json};
 use tauri::Emitter;
 use keyring::{Entry, Result as KeyringResult};
 
+pub mod error;
+use error::{GoblinError, GoblinResult};
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RuntimeStatus {
     pub running: bool,
     pub version: String,
     pub uptime: Option<u64>,
+    pub state: supervisor::AgentState,
 }
 
-#[derive(Clone)]
+/// How long `send_message_to_runtime` will wait for a reply before giving up
+/// on the in-flight request.
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 struct RuntimeState {
     running: bool,
     child_process: Option<Arc<Mutex<Child>>>,
+    stdin: Option<Arc<Mutex<ChildStdin>>>,
+    /// Requests awaiting a reply, keyed by the id we allocated for them.
+    pending: HashMap<String, oneshot::Sender<GoblinResult<JsonValue>>>,
+    started_at: Option<std::time::Instant>,
+    state: supervisor::AgentState,
+    restart_attempts: u32,
+    /// Cumulative cost of every task run this session, seeded from the
+    /// durable store at startup so a restart doesn't reset it to zero.
+    session_cost_total: f64,
+    /// Optional spend ceiling set via `set_cost_budget`; once the session
+    /// total would cross it, in-flight tasks abort their stream.
+    session_cost_ceiling: Option<f64>,
 }
 
 use lazy_static::lazy_static;
 mod memory;
 mod cost_estimator;
+mod protocol;
+pub mod dbctx;
+mod sql;
+mod scheduler;
+mod supervisor;
+mod budget;
+mod embeddings;
+mod cost_tracker;
+mod orchestration_error;
+mod benchmark;
+pub mod progress;
+use progress::{Progress, ProgressRegistry};
+mod tokenizer;
+pub mod config_watch;
+mod mock_runtime;
+mod runtime_trait;
+mod tome;
 
 lazy_static! {
     static ref RUNTIME_STATE: Mutex<RuntimeState> = Mutex::new(RuntimeState {
         running: false,
         child_process: None,
+        stdin: None,
+        pending: HashMap::new(),
+        started_at: None,
+        state: supervisor::AgentState::Stopped,
+        restart_attempts: 0,
+        session_cost_total: 0.0,
+        session_cost_ceiling: None,
     });
 }
 
+/// Owns the child's stdout and routes each decoded line either to the
+/// waiter registered for its `id` or, for unsolicited lines like the
+/// `{ready:true}` handshake, to the log.
+async fn run_reader_loop(stdout: tokio::process::ChildStdout) {
+    let mut reader = BufReader::new(stdout);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) => {
+                println!("Goblin runtime stdout closed");
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                println!("Failed to read from goblin runtime stdout: {}", e);
+                break;
+            }
+        }
+
+        match protocol::parse_incoming(&line) {
+            Some(protocol::IncomingFrame::Reply { id, result }) => {
+                let result = result.map_err(|message| GoblinError::Protocol { id: id.clone(), message });
+                let mut state = RUNTIME_STATE.lock().await;
+                if let Some(sender) = state.pending.remove(&id) {
+                    let _ = sender.send(result);
+                }
+            }
+            Some(protocol::IncomingFrame::Unsolicited(value)) => {
+                println!("Unsolicited goblin runtime message: {}", value);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Drains the child's stderr and forwards each line to the frontend as a
+/// `runtime-log` event, so crash diagnostics aren't silently discarded.
+async fn run_stderr_loop(stderr: tokio::process::ChildStderr, app: tauri::AppHandle) {
+    let mut reader = BufReader::new(stderr);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) => break,
+            Ok(_) => {
+                let trimmed = line.trim();
+                if !trimmed.is_empty() {
+                    let _ = app.emit("runtime-log", json!({ "stream": "stderr", "line": trimmed }));
+                }
+            }
+            Err(_) => break,
+        }
+    }
+}
+
+/// Spawn the goblin-runtime child, wire up its stdout/stderr handling, and
+/// install it as the live runtime in `RUNTIME_STATE`. Used both for the
+/// initial `start_runtime` call and for respawns after a crash.
+async fn spawn_and_track(app: tauri::AppHandle) -> GoblinResult<()> {
+    let mut child = spawn_goblin_runtime().await?;
+    let stdin = child.stdin.take().ok_or_else(|| GoblinError::Spawn("child process stdin not available".to_string()))?;
+    let stdout = child.stdout.take().ok_or_else(|| GoblinError::Spawn("child process stdout not available".to_string()))?;
+    let stderr = child.stderr.take().ok_or_else(|| GoblinError::Spawn("child process stderr not available".to_string()))?;
+
+    tokio::spawn(run_reader_loop(stdout));
+    tokio::spawn(run_stderr_loop(stderr, app.clone()));
+
+    let child_arc = Arc::new(Mutex::new(child));
+
+    {
+        let mut state = RUNTIME_STATE.lock().await;
+        state.child_process = Some(child_arc.clone());
+        state.stdin = Some(Arc::new(Mutex::new(stdin)));
+        if state.state == supervisor::AgentState::Starting {
+            // A fresh `start_runtime`, not a crash respawn: reset the backoff.
+            state.restart_attempts = 0;
+        }
+        state.running = true;
+        state.state = supervisor::AgentState::Running;
+        state.started_at = Some(std::time::Instant::now());
+    }
+
+    tokio::spawn(supervise(app, child_arc));
+
+    Ok(())
+}
+
+/// Waits for the supervised child to exit. A clean `stop_runtime` marks the
+/// state `Stopped` before killing the child, so if we see anything else
+/// here the exit was unexpected: flip to `Crashed`, notify the frontend,
+/// and respawn with exponential backoff up to `MAX_RESTART_ATTEMPTS`.
+async fn supervise(app: tauri::AppHandle, child_arc: Arc<Mutex<Child>>) {
+    {
+        let mut child = child_arc.lock().await;
+        let _ = child.wait().await;
+    }
+
+    let was_deliberate_stop = {
+        let state = RUNTIME_STATE.lock().await;
+        state.state == supervisor::AgentState::Stopped
+    };
+    if was_deliberate_stop {
+        return;
+    }
+
+    let attempt = {
+        let mut state = RUNTIME_STATE.lock().await;
+        state.running = false;
+        state.state = supervisor::AgentState::Crashed;
+        state.started_at = None;
+        state.restart_attempts += 1;
+        state.restart_attempts
+    };
+
+    println!("Goblin runtime exited unexpectedly (restart attempt {})", attempt);
+    let _ = app.emit("runtime-crashed", json!({ "attempt": attempt }));
+
+    if attempt > supervisor::MAX_RESTART_ATTEMPTS {
+        println!("Goblin runtime exceeded {} restart attempts, giving up", supervisor::MAX_RESTART_ATTEMPTS);
+        return;
+    }
+
+    {
+        let mut state = RUNTIME_STATE.lock().await;
+        state.state = supervisor::AgentState::Restarting;
+    }
+    tokio::time::sleep(supervisor::restart_delay(attempt)).await;
+
+    if let Err(e) = spawn_and_track(app).await {
+        println!("Failed to respawn goblin runtime: {}", e);
+    }
+}
+
 // Secure API key storage using system keyring
 fn get_keyring_entry(provider: &str) -> KeyringResult<Entry> {
     Entry::new("goblinos-desktop", provider)
 }
 
-async fn store_api_key_secure(provider: &str, key: &str) -> Result<(), String> {
+async fn store_api_key_secure(provider: &str, key: &str) -> GoblinResult<()> {
     let entry = get_keyring_entry(provider)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+        .map_err(|e| GoblinError::Keyring(format!("Failed to create keyring entry: {}", e)))?;
 
     entry.set_password(key)
-        .map_err(|e| format!("Failed to store API key: {}", e))?;
+        .map_err(|e| GoblinError::Keyring(format!("Failed to store API key: {}", e)))?;
 
     println!("Securely stored API key for provider: {}", provider);
     Ok(())
 }
 
-async fn get_api_key_secure(provider: &str) -> Result<Option<String>, String> {
+async fn get_api_key_secure(provider: &str) -> GoblinResult<Option<String>> {
     let entry = get_keyring_entry(provider)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+        .map_err(|e| GoblinError::Keyring(format!("Failed to create keyring entry: {}", e)))?;
 
     match entry.get_password() {
         Ok(password) => Ok(Some(password)),
         Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(format!("Failed to retrieve API key: {}", e)),
+        Err(e) => Err(GoblinError::Keyring(format!("Failed to retrieve API key: {}", e))),
     }
 }
 
-async fn clear_api_key_secure(provider: &str) -> Result<(), String> {
+async fn clear_api_key_secure(provider: &str) -> GoblinResult<()> {
     let entry = get_keyring_entry(provider)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+        .map_err(|e| GoblinError::Keyring(format!("Failed to create keyring entry: {}", e)))?;
 
     match entry.delete_password() {
         Ok(()) => {
@@ -74,17 +254,17 @@ async fn clear_api_key_secure(provider: &str) -> Result<(), String> {
             println!("API key for provider {} was not found (already cleared)", provider);
             Ok(())
         },
-        Err(e) => Err(format!("Failed to clear API key: {}", e)),
+        Err(e) => Err(GoblinError::Keyring(format!("Failed to clear API key: {}", e))),
     }
 }
 
-async fn spawn_goblin_runtime() -> Result<Child, String> {
+async fn spawn_goblin_runtime() -> GoblinResult<Child> {
     // Find the goblin-runtime package directory
     // Allow overriding with GOBLIN_RUNTIME_DIR env var; otherwise look for common locations
     let runtime_dir = if let Ok(dir) = std::env::var("GOBLIN_RUNTIME_DIR") {
         std::path::PathBuf::from(dir)
     } else {
-        let cwd = std::env::current_dir().map_err(|e| format!("Failed to get current dir: {}", e))?;
+        let cwd = std::env::current_dir()?;
         let candidates = vec![
             cwd.join("packages").join("goblin-runtime"),
             cwd.join("goblin-runtime"),
@@ -102,7 +282,7 @@ async fn spawn_goblin_runtime() -> Result<Child, String> {
         match found {
             Some(p) => p,
             None => {
-                return Err("Goblin runtime directory not found. Set GOBLIN_RUNTIME_DIR or place goblin-runtime in ./packages or ./goblin-runtime".to_string());
+                return Err(GoblinError::Spawn("Goblin runtime directory not found. Set GOBLIN_RUNTIME_DIR or place goblin-runtime in ./packages or ./goblin-runtime".to_string()));
             }
         }
     };
@@ -154,6 +334,9 @@ async fn spawn_goblin_runtime() -> Result<Child, String> {
                         case 'executeTask':
                             result = await runtime.executeTask(message.task);
                             break;
+                        case 'cancelTask':
+                            result = { cancelled: true, taskId: message.taskId };
+                            break;
                         default:
                             throw new Error(`Unknown method: ${message.method}`);
                     }
@@ -175,85 +358,57 @@ async fn spawn_goblin_runtime() -> Result<Child, String> {
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
     .spawn()
-        .map_err(|e| format!("Failed to spawn goblin runtime: {}", e))?;
+        .map_err(|e| GoblinError::Spawn(e.to_string()))?;
 
     Ok(child)
 }
 
-async fn send_message_to_runtime(message: serde_json::Value) -> Result<serde_json::Value, String> {
-    // Get the child process without holding the lock across await points
-    let child_arc = {
-        let state = RUNTIME_STATE.lock().await;
+/// Send a request to the goblin runtime and await its matching reply.
+///
+/// The request is tagged with a fresh, unique id and registered in
+/// `RUNTIME_STATE.pending` *before* it's written, so the background
+/// reader loop (see `run_reader_loop`) can dispatch the reply back to us
+/// as soon as it arrives, however long that takes and regardless of
+/// however many other calls are in flight at the same time.
+async fn send_message_to_runtime(method: &str, fields: serde_json::Value) -> GoblinResult<serde_json::Value> {
+    let id = protocol::new_request_id();
+    let framed = protocol::frame_request(&id, method, fields);
+
+    let (sender, receiver) = oneshot::channel();
+    let stdin_arc = {
+        let mut state = RUNTIME_STATE.lock().await;
         if !state.running {
-            return Err("Runtime is not running".to_string());
+            return Err(GoblinError::RuntimeNotRunning);
         }
-        state.child_process.as_ref().cloned().ok_or("No child process available")?
+        let stdin_arc = state.stdin.as_ref().cloned().ok_or(GoblinError::RuntimeNotRunning)?;
+        state.pending.insert(id.clone(), sender);
+        stdin_arc
     };
 
-    // Clone the message for sending
-    let message_str = message.to_string() + "\n";
-
-    // Send message to child process
     {
-        let mut child = child_arc.lock().await;
-        if let Some(stdin) = child.stdin.as_mut() {
-            stdin.write_all(message_str.as_bytes()).await
-                .map_err(|e| format!("Failed to write to child stdin: {}", e))?;
-            stdin.flush().await
-                .map_err(|e| format!("Failed to flush child stdin: {}", e))?;
-        } else {
-            return Err("Child process stdin not available".to_string());
+        let mut stdin = stdin_arc.lock().await;
+        if let Err(e) = stdin.write_all(framed.as_bytes()).await {
+            RUNTIME_STATE.lock().await.pending.remove(&id);
+            return Err(GoblinError::Io(format!("Failed to write to child stdin: {}", e)));
+        }
+        if let Err(e) = stdin.flush().await {
+            RUNTIME_STATE.lock().await.pending.remove(&id);
+            return Err(GoblinError::Io(format!("Failed to flush child stdin: {}", e)));
         }
     }
 
-    // Read response from child process stdout
-    // For now, we'll use a simple approach - read a line and parse JSON
-    // In a real implementation, you'd want more robust message framing
-    use tokio::io::{AsyncBufReadExt, BufReader};
-
-    let mut child = child_arc.lock().await;
-    if let Some(stdout) = child.stdout.as_mut() {
-        let mut reader = BufReader::new(stdout);
-        let mut line = String::new();
-
-        // Read lines until we get a valid JSON response
-        loop {
-            line.clear();
-            let bytes_read = reader.read_line(&mut line).await
-                .map_err(|e| format!("Failed to read from child stdout: {}", e))?;
-
-            if bytes_read == 0 {
-                return Err("Child process stdout closed unexpectedly".to_string());
-            }
-
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            // Try to parse as JSON
-            match serde_json::from_str::<JsonValue>(trimmed) {
-                Ok(response) => {
-                    // Check if this is an error response
-                    if let Some(error) = response.get("error") {
-                        return Err(error.as_str().unwrap_or("Unknown error").to_string());
-                    }
-                    // Return the result field if present, otherwise the whole response
-                    return Ok(response.get("result").unwrap_or(&response).clone());
-                }
-                Err(_) => {
-                    // Not valid JSON, continue reading
-                    continue;
-                }
-            }
+    match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(GoblinError::Protocol { id: id.clone(), message: "goblin runtime closed before replying".to_string() }),
+        Err(_) => {
+            RUNTIME_STATE.lock().await.pending.remove(&id);
+            Err(GoblinError::Timeout)
         }
-    } else {
-        return Err("Child process stdout not available".to_string());
     }
 }
 
 #[tauri::command]
-pub async fn start_runtime() -> Result<String, String> {
+pub async fn start_runtime(app: tauri::AppHandle) -> GoblinResult<String> {
     // Check if already running
     {
         let state = RUNTIME_STATE.lock().await;
@@ -263,24 +418,27 @@ pub async fn start_runtime() -> Result<String, String> {
     }
 
     println!("Starting goblin runtime...");
+    {
+        let mut state = RUNTIME_STATE.lock().await;
+        state.state = supervisor::AgentState::Starting;
+    }
 
-    match spawn_goblin_runtime().await {
-        Ok(child) => {
-            let mut state = RUNTIME_STATE.lock().await;
-            state.child_process = Some(Arc::new(Mutex::new(child)));
-            state.running = true;
+    match spawn_and_track(app).await {
+        Ok(()) => {
             println!("Goblin runtime started successfully");
             Ok("Runtime started".to_string())
         }
         Err(e) => {
             println!("Failed to start goblin runtime: {}", e);
+            let mut state = RUNTIME_STATE.lock().await;
+            state.state = supervisor::AgentState::Stopped;
             Err(e)
         }
     }
 }
 
 #[tauri::command]
-pub async fn stop_runtime() -> Result<String, String> {
+pub async fn stop_runtime() -> GoblinResult<String> {
     let child_arc = {
         let mut state = RUNTIME_STATE.lock().await;
 
@@ -288,6 +446,9 @@ pub async fn stop_runtime() -> Result<String, String> {
             return Ok("Runtime is not running".to_string());
         }
 
+        // Mark the stop as deliberate *before* killing the child so the
+        // supervisor task doesn't treat this exit as a crash.
+        state.state = supervisor::AgentState::Stopped;
         state.child_process.take()
     };
 
@@ -302,6 +463,9 @@ pub async fn stop_runtime() -> Result<String, String> {
         let mut state = RUNTIME_STATE.lock().await;
         state.running = false;
         state.child_process = None;
+        state.stdin = None;
+        state.pending.clear();
+        state.started_at = None;
     }
 
     println!("Runtime stopped");
@@ -309,10 +473,10 @@ pub async fn stop_runtime() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn send_event(event: String) -> Result<String, String> {
+pub async fn send_event(event: String) -> GoblinResult<String> {
     let state = RUNTIME_STATE.lock().await;
     if !state.running {
-        return Err("Runtime is not running".to_string());
+        return Err(GoblinError::RuntimeNotRunning);
     }
 
     // TODO: Implement actual event sending logic
@@ -322,15 +486,45 @@ pub async fn send_event(event: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn status() -> Result<RuntimeStatus, String> {
+pub async fn status() -> GoblinResult<RuntimeStatus> {
     let state = RUNTIME_STATE.lock().await;
     Ok(RuntimeStatus {
         running: state.running,
         version: "0.1.0".to_string(),
-        uptime: None,
+        uptime: state.started_at.map(|t| t.elapsed().as_secs()),
+        state: state.state,
     })
 }
 
+/// Set (or clear, with `None`) the session-wide spend ceiling that
+/// `execute_task_impl` checks its running total against.
+#[tauri::command]
+pub async fn set_cost_budget(ceiling_usd: Option<f64>) -> GoblinResult<()> {
+    let mut state = RUNTIME_STATE.lock().await;
+    state.session_cost_ceiling = ceiling_usd;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_cost_usage() -> GoblinResult<JsonValue> {
+    let state = RUNTIME_STATE.lock().await;
+    Ok(json!({
+        "session_cost_total": state.session_cost_total,
+        "session_cost_ceiling": state.session_cost_ceiling,
+    }))
+}
+
+/// Seed `RUNTIME_STATE`'s session cost counter from the durable store so a
+/// restart doesn't silently reset spend tracking back to zero. Called once
+/// during app setup, after `dbctx::init`.
+pub async fn seed_session_cost_usage() -> GoblinResult<()> {
+    let totals = dbctx::cost_by_provider(None, None).await?;
+    let total: f64 = totals.values().sum();
+    let mut state = RUNTIME_STATE.lock().await;
+    state.session_cost_total = total;
+    Ok(())
+}
+
 // --- Non-command helpers for the main process to call ---
 // These are simple stubs for now and should be replaced with
 // actual integration with the goblin-runtime (child process, napi, etc.)
@@ -348,13 +542,8 @@ pub struct HistoryEntry {
     pub message: String,
 }
 
-pub async fn list_goblins_impl() -> Result<Vec<String>, String> {
-    let message = json!({
-        "id": "list_goblins",
-        "method": "listGoblins"
-    });
-
-    let response = send_message_to_runtime(message).await?;
+pub async fn list_goblins_impl() -> GoblinResult<Vec<String>> {
+    let response = send_message_to_runtime("listGoblins", json!({})).await?;
 
     // Parse the response as an array of strings
     match response {
@@ -367,18 +556,14 @@ pub async fn list_goblins_impl() -> Result<Vec<String>, String> {
             }
             Ok(goblins)
         }
-        _ => Err(format!("Unexpected response format: {:?}", response))
+        other => Err(GoblinError::UnexpectedResponse(other))
     }
 }
 
-pub async fn get_goblin_stats_impl(goblin_id: &str) -> Result<GoblinStats, String> {
-    let message = json!({
-        "id": format!("stats_{}", goblin_id),
-        "method": "getGoblinStats",
+pub async fn get_goblin_stats_impl(goblin_id: &str) -> GoblinResult<GoblinStats> {
+    let response = send_message_to_runtime("getGoblinStats", json!({
         "goblinId": goblin_id
-    });
-
-    let response = send_message_to_runtime(message).await?;
+    })).await?;
 
     // Parse the response as GoblinStats
     match response {
@@ -391,8 +576,12 @@ pub async fn get_goblin_stats_impl(goblin_id: &str) -> Result<GoblinStats, Strin
                 .and_then(|v| v.as_str())
                 .unwrap_or("unknown")
                 .to_string();
-            let last_seen = obj.get("lastSeen")
-                .and_then(|v| v.as_u64());
+            let last_seen = match obj.get("lastSeen").and_then(|v| v.as_u64()) {
+                Some(ts) => Some(ts),
+                // The runtime doesn't always know when it last saw a goblin
+                // across restarts - the durable store does.
+                None => dbctx::get_goblin_last_seen(goblin_id).await.unwrap_or(None),
+            };
 
             Ok(GoblinStats {
                 id,
@@ -400,18 +589,16 @@ pub async fn get_goblin_stats_impl(goblin_id: &str) -> Result<GoblinStats, Strin
                 last_seen,
             })
         }
-        _ => Err(format!("Unexpected response format for goblin stats: {:?}", response))
+        other => Err(GoblinError::UnexpectedResponse(other))
     }
 }
 
-pub async fn get_history_impl(goblin_id: &str, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
-    let message = json!({
-        "id": format!("history_{}", goblin_id),
-        "method": "getGoblinHistory",
+pub async fn get_history_impl(goblin_id: &str, limit: Option<usize>) -> GoblinResult<Vec<HistoryEntry>> {
+    let response = send_message_to_runtime("getGoblinHistory", json!({
         "goblinId": goblin_id,
         "limit": limit.unwrap_or(10)
-    });
-    match send_message_to_runtime(message).await {
+    })).await;
+    match response {
         Ok(response) => {
             // Parse the response as an array of HistoryEntry
             match response {
@@ -432,22 +619,24 @@ pub async fn get_history_impl(goblin_id: &str, limit: Option<usize>) -> Result<V
                     }
                     Ok(history)
                 }
-                _ => Err(format!("Unexpected response format for history: {:?}", response))
+                other => Err(GoblinError::UnexpectedResponse(other))
             }
         }
         Err(_) => {
-            // Fallback to in-memory store for demo simplicity
+            // Runtime unreachable (or not running) - fall back to the
+            // durable store (or, if that isn't initialized, `memory`'s
+            // in-process cache) so history survives even when the process
+            // currently has no live goblin-runtime child.
             let entries = memory::get_history(goblin_id, limit).await;
-            let mut history = Vec::new();
-            for (ts, message) in entries {
-                history.push(HistoryEntry { ts, message });
-            }
-            Ok(history)
+            Ok(entries
+                .into_iter()
+                .map(|(ts, message)| HistoryEntry { ts, message })
+                .collect())
         }
     }
 }
 
-fn get_system_prompt(task: &str) -> &'static str {
+pub(crate) fn get_system_prompt(task: &str) -> &'static str {
     match task {
         "document this code" => "You are an expert technical writer. Add clear, concise comments to the following code. Then, generate a markdown block with the function signature, a description of what it does, its parameters, and what it returns.",
         "write a unit test" => "You are an expert software engineer specializing in testing. Write a simple, effective unit test for the following code using the Jest framework. Provide only the code block for the test.",
@@ -455,132 +644,283 @@ fn get_system_prompt(task: &str) -> &'static str {
     }
 }
 
-pub async fn execute_task_impl(app: tauri::AppHandle, goblin_id: &str, task: &str, args: Option<JsonValue>) -> Result<String, String> {
+/// Pull the id a dependent step or the UI should watch for out of a step's
+/// raw provider response, falling back to a synthesized one when the
+/// response didn't carry its own.
+fn extract_task_id(response: &JsonValue, goblin_id: &str, task: &str) -> String {
+    match response {
+        JsonValue::Object(obj) => obj
+            .get("taskId")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("task_{}_{}", goblin_id, task)),
+        _ => format!("task_{}_{}", goblin_id, task),
+    }
+}
+
+pub async fn execute_task_impl(app: tauri::AppHandle, goblin_id: &str, task: &str, args: Option<JsonValue>, progress: Arc<ProgressRegistry>) -> GoblinResult<String> {
     // Determine system prompt based on task
     let system_prompt = get_system_prompt(task);
 
-    let message = json!({
-        "id": format!("task_{}_{}", goblin_id, task),
-        "method": "executeTask",
-        "task": {
-            "goblin": goblin_id,
-            "task": task,
-            "system_prompt": system_prompt,
-            "context": args
-        }
+    // Fetch this step's raw result through the pluggable `Runtime` seam, so
+    // mock mode (see `mock_runtime`) can stand in for the real
+    // goblin-runtime child process without this function needing to know
+    // which one it's talking to.
+    let response = runtime_trait::current().call_step(goblin_id, task, system_prompt, args.clone()).await?;
+    let task_id = extract_task_id(&response, goblin_id, task);
+
+    // Run the actual work (streaming, budget checks, the durable
+    // write-through) in the background and hand the task id back
+    // immediately - direct `execute_task` IPC callers follow along via the
+    // `task-stream` events and `progress` channel `run_task_to_completion`
+    // publishes as it goes, rather than waiting here. `execute_task_and_await`
+    // below runs the same work in-line for callers that need the real
+    // result, not just an id to watch for.
+    let task_id_for_spawn = task_id.clone();
+    let app_for_spawn = app.clone();
+    let goblin_id_owned = goblin_id.to_string();
+    let task_owned = task.to_string();
+    tokio::spawn(async move {
+        let _ = run_task_to_completion(app_for_spawn, goblin_id_owned, task_owned, args, progress, task_id_for_spawn, response).await;
     });
 
-    // Send the task execution message
-    let response = send_message_to_runtime(message).await?;
+    Ok(task_id)
+}
+
+/// Scheduler-facing variant of `execute_task_impl`: runs the exact same
+/// work, but awaits it to completion and returns the real result payload
+/// instead of an opaque task id. `run_plan` needs this - a dependent step's
+/// `[stepN]` reference (see `splice_dependency_results`) has to resolve to
+/// actual output, and a step can't be marked `Completed` (unblocking its
+/// dependents) before its work has actually finished.
+pub async fn execute_task_and_await(app: tauri::AppHandle, goblin_id: &str, task: &str, args: Option<JsonValue>, progress: Arc<ProgressRegistry>) -> GoblinResult<JsonValue> {
+    let system_prompt = get_system_prompt(task);
+    let response = runtime_trait::current().call_step(goblin_id, task, system_prompt, args.clone()).await?;
+    let task_id = extract_task_id(&response, goblin_id, task);
+    run_task_to_completion(app, goblin_id.to_string(), task.to_string(), args, progress, task_id, response).await
+}
 
-    // Extract task ID from response if available
-    let task_id = match response {
+/// Stream `response`'s chunks, track cost against `args`' `TaskBudget` and
+/// the session ceiling, write the outcome through to the durable store, and
+/// return the final result payload. Shared by `execute_task_impl` (spawned
+/// in the background, result discarded - a direct `execute_task` call
+/// already returned) and `execute_task_and_await` (awaited in-line, result
+/// used as the step's dependency output). A mock-injected failure maps to a
+/// retryable error, the same as a real flaky provider would; a budget being
+/// exceeded mid-stream maps to a non-retryable one, since retrying would
+/// just hit the same budget again.
+async fn run_task_to_completion(
+    app: tauri::AppHandle,
+    goblin_id: String,
+    task: String,
+    args: Option<JsonValue>,
+    progress: Arc<ProgressRegistry>,
+    task_id: String,
+    response: JsonValue,
+) -> GoblinResult<JsonValue> {
+    let started_at = Utc::now().timestamp_millis() as u64;
+    // Simulate streaming output chunks based on the response
+    let chunks = match response {
         JsonValue::Object(ref obj) => {
-            obj.get("taskId")
-                .and_then(|v| v.as_str())
-                .unwrap_or(&format!("task_{}_{}", goblin_id, task))
-                .to_string()
+            obj.get("chunks")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.len())
+                .unwrap_or(5)
         }
-        _ => format!("task_{}_{}", goblin_id, task)
+        _ => 5
     };
 
-    // Start streaming simulation based on the actual response
-    let task_id_for_closure = task_id.clone();
-    let app_clone = app.clone();
-    let goblin_id = goblin_id.to_string();
-    let task = task.to_string();
-    let args = args.clone();
+    let progress_tx = progress.register(app.clone(), &task_id, Progress::new(chunks)).await;
 
-    tokio::spawn(async move {
-        let task_id_clone = task_id_for_closure;
-        // Simulate streaming output chunks based on the response
-        let chunks = match response {
-            JsonValue::Object(ref obj) => {
-                obj.get("chunks")
-                    .and_then(|v| v.as_array())
-                    .map(|arr| arr.len())
-                    .unwrap_or(5)
-            }
-            _ => 5
+    // Identify provider and model from args for cost estimation
+    let provider_name = args.as_ref().and_then(|a| a.get("provider")).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let model_name = args.as_ref().and_then(|a| a.get("model")).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let task_budget = budget::TaskBudget::from_args(args.as_ref());
+
+    let mut cumulative_cost = 0.0f64;
+    let mut cumulative_tokens = 0usize;
+    let mut aborted_budget = false;
+    let mut mock_failed = false;
+    let mock_mode = mock_runtime::enabled();
+
+    for i in 0..chunks {
+        tokio::time::sleep(if mock_mode { mock_runtime::latency() } else { tokio::time::Duration::from_millis(500) }).await;
+
+        if mock_mode && mock_runtime::should_fail() {
+            mock_failed = true;
+            break;
+        }
+        let chunk_text = format!("Chunk {} for task {} on {}", i, task, goblin_id);
+        let provider_for_calc = provider_name.clone().unwrap_or_else(|| "unknown".to_string());
+        let token_count = tokenizer::count_tokens(&provider_for_calc, model_name.as_deref(), &chunk_text);
+        let cost_delta = cost_estimator::estimate_cost(&provider_for_calc, model_name.clone().as_deref(), token_count);
+
+        cumulative_cost += cost_delta;
+        cumulative_tokens += token_count;
+
+        let payload = json!({
+            "taskId": task_id,
+            "chunk": chunk_text,
+            "progress": i as f32 / (chunks - 1) as f32,
+            "provider": provider_for_calc,
+            "cost_delta": cost_delta,
+            "token_count": token_count
+        });
+        let _ = app.emit("task-stream", payload);
+        let _ = progress_tx.send(Progress {
+            step: i + 1,
+            total_steps: chunks,
+            bytes_or_tokens_done: cumulative_tokens,
+            message: format!("chunk {} of {}", i + 1, chunks),
+        });
+
+        let session_exceeded = {
+            let state = RUNTIME_STATE.lock().await;
+            state.session_cost_ceiling
+                .map_or(false, |ceiling| state.session_cost_total + cumulative_cost > ceiling)
         };
 
-        // Identify provider and model from args for cost estimation
-        let provider_name = args.as_ref().and_then(|a| a.get("provider")).and_then(|v| v.as_str()).map(|s| s.to_string());
-        let model_name = args.as_ref().and_then(|a| a.get("model")).and_then(|v| v.as_str()).map(|s| s.to_string());
-
-        for i in 0..chunks {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            let chunk_text = format!("Chunk {} for task {} on {}", i, task, goblin_id);
-            let token_count = cost_estimator::estimate_tokens_from_text(&chunk_text);
-            let provider_for_calc = provider_name.clone().unwrap_or_else(|| "unknown".to_string());
-            let cost_delta = cost_estimator::estimate_cost(&provider_for_calc, model_name.clone().as_deref(), token_count);
-
-            let payload = json!({
-                "taskId": task_id_clone,
-                "chunk": chunk_text,
-                "progress": i as f32 / (chunks - 1) as f32,
-                "provider": provider_for_calc,
-                "cost_delta": cost_delta,
-                "token_count": token_count
-            });
-            let _ = app_clone.emit("task-stream", payload);
+        if task_budget.exceeded(cumulative_cost, cumulative_tokens) || session_exceeded {
+            aborted_budget = true;
+            break;
         }
+    }
 
-        // Emit final result
-        let total_cost: f64 = (0..chunks).map(|i| {
-            // approximate each chunk token length used; reuse chunk text logic from above
-            let chunk_text = format!("Chunk {} for task {} on {}", i, task, goblin_id);
-            let token_count = cost_estimator::estimate_tokens_from_text(&chunk_text);
-            cost_estimator::estimate_cost(&provider_name.clone().unwrap_or_else(|| "unknown".to_string()), model_name.clone().as_deref(), token_count)
-        }).sum();
-        let result = json!({
-            "taskId": task_id_clone,
-            "goblin": goblin_id,
-            "task": task,
-            "args": args,
-            "result": response,
+    if mock_failed {
+        // Synthetic failure injected via GOBLIN_MOCK_FAIL_RATE, to
+        // exercise this same error path without a real flaky provider.
+        let _ = app.emit("task-stream", json!({
+            "taskId": task_id,
+            "status": "failed",
             "provider": provider_name,
-            "cost": total_cost
-        });
-        let _ = app_clone.emit("task-stream", result);
-    });
+            "cost": cumulative_cost,
+            "tokens": cumulative_tokens
+        }));
 
-    Ok(task_id)
+        let completed_at = Utc::now().timestamp_millis() as u64;
+        RUNTIME_STATE.lock().await.session_cost_total += cumulative_cost;
+        let _ = dbctx::record_task(
+            &task_id,
+            &goblin_id,
+            &task,
+            "failed",
+            provider_name.as_deref(),
+            model_name.as_deref(),
+            cumulative_cost,
+            started_at,
+            Some(completed_at),
+        ).await;
+        let _ = dbctx::record_history_entry(&goblin_id, completed_at, &format!("Mock-failed task '{}' (cost {:.6})", task, cumulative_cost)).await;
+        let _ = dbctx::upsert_goblin_last_seen(&goblin_id, completed_at).await;
+        progress.unregister(&task_id).await;
+        return Err(GoblinError::Io(format!("mock-failed task '{}' (cost {:.6})", task, cumulative_cost)));
+    }
+
+    if aborted_budget {
+        // Ask the runtime to stop work on this task; we don't wait on a
+        // reply since the stream is already being torn down either way.
+        let _ = send_message_to_runtime("cancelTask", json!({ "taskId": task_id })).await;
+
+        let _ = app.emit("task-stream", json!({
+            "taskId": task_id,
+            "status": "aborted_budget",
+            "provider": provider_name,
+            "cost": cumulative_cost,
+            "tokens": cumulative_tokens
+        }));
+
+        let completed_at = Utc::now().timestamp_millis() as u64;
+        RUNTIME_STATE.lock().await.session_cost_total += cumulative_cost;
+        let _ = dbctx::record_task(
+            &task_id,
+            &goblin_id,
+            &task,
+            "aborted_budget",
+            provider_name.as_deref(),
+            model_name.as_deref(),
+            cumulative_cost,
+            started_at,
+            Some(completed_at),
+        ).await;
+        let _ = dbctx::record_history_entry(&goblin_id, completed_at, &format!("Aborted task '{}' over budget (cost {:.6})", task, cumulative_cost)).await;
+        let _ = dbctx::upsert_goblin_last_seen(&goblin_id, completed_at).await;
+        progress.unregister(&task_id).await;
+        return Err(GoblinError::Io(format!("task '{}' aborted: over budget (cost {:.6})", task, cumulative_cost)));
+    }
+
+    // Emit final result
+    let result = json!({
+        "taskId": task_id,
+        "goblin": goblin_id,
+        "task": task,
+        "args": args,
+        "result": response,
+        "provider": provider_name,
+        "cost": cumulative_cost
+    });
+    let _ = app.emit("task-stream", result.clone());
+
+    // Write through to the durable store so the transcript and
+    // cumulative cost survive a restart.
+    let completed_at = Utc::now().timestamp_millis() as u64;
+    RUNTIME_STATE.lock().await.session_cost_total += cumulative_cost;
+    let _ = dbctx::record_task(
+        &task_id,
+        &goblin_id,
+        &task,
+        "completed",
+        provider_name.as_deref(),
+        model_name.as_deref(),
+        cumulative_cost,
+        started_at,
+        Some(completed_at),
+    ).await;
+    let _ = dbctx::record_history_entry(&goblin_id, completed_at, &format!("Completed task '{}' (cost {:.6})", task, cumulative_cost)).await;
+    let _ = dbctx::upsert_goblin_last_seen(&goblin_id, completed_at).await;
+    progress.unregister(&task_id).await;
+
+    Ok(result)
 }
 
 // --- API Key Management Functions ---
 
-pub async fn store_api_key_impl(provider: &str, key: &str) -> Result<(), String> {
+pub async fn store_api_key_impl(provider: &str, key: &str) -> GoblinResult<()> {
     store_api_key_secure(provider, key).await
 }
 
-pub async fn get_api_key_impl(provider: &str) -> Result<Option<String>, String> {
+pub async fn get_api_key_impl(provider: &str) -> GoblinResult<Option<String>> {
     get_api_key_secure(provider).await
 }
 
-pub async fn clear_api_key_impl(provider: &str) -> Result<(), String> {
+pub async fn clear_api_key_impl(provider: &str) -> GoblinResult<()> {
     clear_api_key_secure(provider).await
 }
 
-pub async fn set_provider_api_key_impl(provider: &str, key: &str) -> Result<(), String> {
+pub async fn set_provider_api_key_impl(provider: &str, key: &str) -> GoblinResult<()> {
     // For now, this is the same as store_api_key
     // In the future, this could have different logic for provider-specific handling
     store_api_key_impl(provider, key).await
 }
 
-pub async fn get_providers_impl() -> Result<Vec<String>, String> {
+pub async fn get_providers_impl() -> GoblinResult<Vec<String>> {
     // Return available providers, with Ollama first for out-of-the-box experience
     // TODO: This could query the goblin-runtime for available providers
-    Ok(vec![
+    let mut providers = vec![
         "ollama".to_string(),
         "openai".to_string(),
         "anthropic".to_string(),
         "gemini".to_string(),
         "deepseek".to_string(),
-    ])
+    ];
+    if mock_runtime::enabled() {
+        // Surface the mock provider too, so the UI has something to route
+        // requests to when running offline.
+        providers.insert(0, mock_runtime::MOCK_PROVIDER.to_string());
+    }
+    Ok(providers)
 }
 
-pub async fn get_provider_models_impl(provider: &str) -> Result<Vec<String>, String> {
+pub async fn get_provider_models_impl(provider: &str) -> GoblinResult<Vec<String>> {
     // Return models for the given provider
     // TODO: This could query the goblin-runtime for actual available models
     match provider {
@@ -607,6 +947,7 @@ pub async fn get_provider_models_impl(provider: &str) -> Result<Vec<String>, Str
             "deepseek-chat".to_string(),
             "deepseek-coder".to_string(),
         ]),
+        p if p == mock_runtime::MOCK_PROVIDER && mock_runtime::enabled() => Ok(vec![mock_runtime::MOCK_MODEL.to_string()]),
         _ => Ok(vec![]),
     }
 }
@@ -614,12 +955,15 @@ pub async fn get_provider_models_impl(provider: &str) -> Result<Vec<String>, Str
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct OrchestrationStepResult {
     pub id: String,
+    pub name: Option<String>,
     pub goblin: String,
     pub task: String,
-    pub status: String,
+    pub depends_on: Vec<String>,
+    pub state: scheduler::StepState,
     pub result: Option<JsonValue>,
     pub started_at: Option<u64>,
     pub completed_at: Option<u64>,
+    pub attempts: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -631,107 +975,530 @@ pub struct OrchestrationPlanResult {
     pub status: String,
 }
 
-/// Execute a plan by parsing a very small subset of orchestration syntax.
-/// This is a pragmatic fallback: split on THEN and treat tokens as sequential steps.
-pub async fn execute_orchestration_impl(app: tauri::AppHandle, text: &str, default_goblin: Option<String>) -> Result<JsonValue, String> {
-    let now = chrono::Utc::now().timestamp_millis() as u64;
-    let mut steps: Vec<OrchestrationStepResult> = vec![];
+fn pending_steps_from_specs(specs: &[scheduler::StepSpec]) -> Vec<OrchestrationStepResult> {
+    specs
+        .iter()
+        .map(|spec| OrchestrationStepResult {
+            id: spec.id.clone(),
+            name: spec.name.clone(),
+            goblin: spec.goblin.clone(),
+            task: spec.task.clone(),
+            depends_on: spec.depends_on.clone(),
+            state: scheduler::StepState::Pending,
+            result: None,
+            started_at: None,
+            completed_at: None,
+            attempts: 0,
+        })
+        .collect()
+}
 
+/// Route every `auto_route` step in `specs` to the best-matching goblin by
+/// capability embedding (see `embeddings::route_task`), leaving steps with
+/// an explicit `goblin:` prefix untouched.
+async fn apply_auto_routing(specs: &mut [scheduler::StepSpec], default_goblin: &str) {
+    for spec in specs.iter_mut() {
+        if spec.auto_route {
+            spec.goblin = embeddings::route_task(&spec.task, default_goblin).await;
+        }
+    }
+}
+
+/// Rough cost/token estimate for a single step's task text - a lighter
+/// version of the per-step math in `estimate_cost_impl` (no code-input
+/// side channel), used for `CostTracker` admission checks and for
+/// cost-based step prioritization.
+pub(crate) fn estimate_step_cost(task: &str, provider: Option<&str>) -> (f64, usize) {
+    let provider_name = provider.unwrap_or("openai");
+    let task_tokens = tokenizer::count_tokens(provider_name, None, task);
+    let estimated_output_tokens = task_tokens * 2;
+    let total_tokens = task_tokens + estimated_output_tokens;
+    let cost_per_token = cost_estimator::cost_per_token(provider_name, None);
+    (total_tokens as f64 * cost_per_token, total_tokens)
+}
+
+/// Replace every `[stepN]` reference in `task` with that dependency's
+/// result, so a step can consume what an earlier (or, via `needs`, later-
+/// declared but earlier-running) step produced. References to a step not in
+/// `dep_results` - i.e. one that isn't actually a dependency - are left as-is.
+pub(crate) fn splice_dependency_results(task: &str, dep_results: &HashMap<String, JsonValue>) -> String {
+    let mut spliced = task.to_string();
+    for (id, result) in dep_results {
+        let needle = format!("[{}]", id);
+        if spliced.contains(&needle) {
+            spliced = spliced.replace(&needle, &dependency_output_text(result));
+        }
+    }
+    spliced
+}
+
+/// Pull the text a dependent step should actually see out of a completed
+/// step's full result payload (the shape `execute_task_and_await` returns):
+/// the provider's own response under `result`, or the whole payload
+/// stringified if that isn't a plain string. Never `taskId` - that's an
+/// internal identifier, not the step's output.
+pub(crate) fn dependency_output_text(result: &JsonValue) -> String {
+    match result.get("result") {
+        Some(JsonValue::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => result.to_string(),
+    }
+}
+
+/// Stable-sort `specs` by estimated cost ascending, so cheap steps get
+/// admission-checked (and spawned) before expensive ones when several
+/// steps are ready at once and the budget is tight. Safe to reorder freely:
+/// `scheduler::run` determines readiness from each step's `depends_on` set,
+/// not from its position in this list, so resorting never changes which
+/// steps are allowed to run before which.
+fn prioritize_by_cost(specs: &mut [scheduler::StepSpec]) {
+    specs.sort_by(|a, b| {
+        let (cost_a, _) = estimate_step_cost(&a.task, None);
+        let (cost_b, _) = estimate_step_cost(&b.task, None);
+        cost_a.partial_cmp(&cost_b).unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Default cap on concurrently-running steps when a plan doesn't specify its
+/// own `max_concurrency` - generous enough for typical fan-out plans without
+/// letting a huge plan flood the goblin runtime with requests all at once.
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// Execute a plan parsed into a DAG (see `scheduler`): `THEN` is a barrier
+/// between stages, `AND` groups steps within a stage that may run
+/// concurrently, and `needs`/`-> name`/`[stepN]` let a step depend on a
+/// specific other one (whose result is spliced into its task text). Ready
+/// steps run as soon as their dependencies complete, bounded by
+/// `max_concurrency` (default `DEFAULT_MAX_CONCURRENCY`) steps running at
+/// once; a failed or skipped dependency skips its dependents instead of
+/// running them.
+///
+/// `budget_usd`/`budget_tokens` (either or both, optional) cap total plan
+/// spend: before a step runs its estimated cost is checked against a
+/// `CostTracker` shared across all steps, and a step that would cross the
+/// budget is marked `deferred` instead of running.
+///
+/// Each step's execute closure reports failures as a typed
+/// `orchestration_error::OrchestrationError`; `scheduler::run` retries the
+/// retryable ones per `RetryPolicy::default()` and fails the rest outright.
+/// `abort_on_failure` (default `false`, preserving the old behavior) decides
+/// whether a terminal failure stops any not-yet-started step in the rest of
+/// the plan, or whether unrelated branches keep running regardless.
+pub async fn execute_orchestration_impl(
+    app: tauri::AppHandle,
+    text: &str,
+    default_goblin: Option<String>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: Option<usize>,
+    progress: Arc<ProgressRegistry>,
+) -> GoblinResult<JsonValue> {
+    execute_orchestration_core(app, text, default_goblin, budget_usd, budget_tokens, abort_on_failure, max_concurrency, progress, |_| {}).await
+}
+
+/// Same as `execute_orchestration_impl`, but also calls `on_step` with each
+/// step's `OrchestrationStepResult` the moment it reaches `completed` or
+/// `failed`, instead of only returning the full plan once every step is
+/// done. Used by `webhook::orchestrate` to stream progress over HTTP as it
+/// happens.
+pub async fn execute_orchestration_streamed(
+    app: tauri::AppHandle,
+    text: &str,
+    default_goblin: Option<String>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: Option<usize>,
+    progress: Arc<ProgressRegistry>,
+    on_step: impl FnMut(OrchestrationStepResult) + Send + 'static,
+) -> GoblinResult<JsonValue> {
+    execute_orchestration_core(app, text, default_goblin, budget_usd, budget_tokens, abort_on_failure, max_concurrency, progress, on_step).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn execute_orchestration_core(
+    app: tauri::AppHandle,
+    text: &str,
+    default_goblin: Option<String>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: Option<usize>,
+    progress: Arc<ProgressRegistry>,
+    on_step: impl FnMut(OrchestrationStepResult) + Send + 'static,
+) -> GoblinResult<JsonValue> {
+    let now = chrono::Utc::now().timestamp_millis() as u64;
     let default_goblin_id = default_goblin.unwrap_or_else(|| "websmith".to_string());
 
-    // Very naive parser: split by 'THEN'
-    let tokens: Vec<&str> = text.split("THEN").map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-    for (idx, token) in tokens.iter().enumerate() {
-        // If token contains ':', assume goblinId: task
-        let (goblin, task) = if let Some(pos) = token.find(":") {
-            (
-                token[..pos].trim().to_string(),
-                token[pos + 1..].trim().to_string(),
-            )
-        } else {
-            (default_goblin_id.clone(), token.to_string())
-        };
+    let mut specs = scheduler::parse_plan(text, &default_goblin_id)?;
+    apply_auto_routing(&mut specs, &default_goblin_id).await;
+    prioritize_by_cost(&mut specs);
+    let plan_id = format!("plan_{}", now);
+
+    run_plan(
+        app,
+        plan_id,
+        text.to_string(),
+        default_goblin_id,
+        now,
+        specs,
+        Vec::new(),
+        budget_usd,
+        budget_tokens,
+        abort_on_failure,
+        max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY),
+        progress,
+        on_step,
+    )
+    .await
+}
 
-        let id = format!("plan_step_{}_{}", now, idx);
-        steps.push(OrchestrationStepResult {
-            id: id.clone(),
-            goblin: goblin.clone(),
-            task: task.clone(),
-            status: "pending".to_string(),
-            result: None,
-            started_at: None,
-            completed_at: None,
-        });
+/// Run a goblin's scripted "tome" (see `config::load_goblin_tome`): a
+/// sequenced, possibly-branching program of provider calls, interpreted by
+/// `tome::run_tome_program` through the `Runtime` trait rather than
+/// compiled into `scheduler`'s concurrent DAG - a tome needs to decide what
+/// to call next based on what an earlier call returned, which the DAG has
+/// no construct for. This is what turns a goblin from a one-shot provider
+/// caller into a reusable, programmable agent.
+pub async fn run_tome_impl(app: tauri::AppHandle, goblin_id: &str, progress: Arc<ProgressRegistry>) -> GoblinResult<JsonValue> {
+    let tome_text = config::load_goblin_tome(goblin_id)
+        .map_err(GoblinError::Io)?
+        .ok_or_else(|| GoblinError::Io(format!("Goblin '{}' has no tome defined in goblins.yaml", goblin_id)))?;
+
+    let program = tome::parse_tome(&tome_text, goblin_id).map_err(GoblinError::Io)?;
+    let runtime = runtime_trait::current();
+    let tracker = cost_tracker::CostTracker::new(None, None);
+    let progress_id = format!("tome_{}", goblin_id);
+
+    let results = tome::run_tome_program(&app, runtime.as_ref(), &program, &tracker, &progress, &progress_id).await?;
+
+    Ok(json!({
+        "goblin": goblin_id,
+        "steps": results,
+        "cost": tracker.spent_usd()
+    }))
+}
+
+/// Resume a plan previously interrupted mid-run (process restart, crash):
+/// reload its stable state from the store, re-derive the same DAG from its
+/// stored orchestration text, drop the already-`Completed` steps (and strip
+/// them from the remaining steps' `depends_on`, since `scheduler::run`
+/// decides readiness purely from that set, not list position - the same
+/// invariant `prioritize_by_cost` relies on), and run only what's left.
+pub async fn resume_orchestration_impl(app: tauri::AppHandle, plan_id: &str, progress: Arc<ProgressRegistry>) -> GoblinResult<JsonValue> {
+    let stored = dbctx::load_plan(plan_id)
+        .await?
+        .ok_or_else(|| GoblinError::Io(format!("No orchestration plan found with id {}", plan_id)))?;
+
+    let completed_ids: std::collections::HashSet<String> = stored
+        .plan
+        .steps
+        .iter()
+        .filter(|s| s.state == scheduler::StepState::Completed)
+        .map(|s| s.id.clone())
+        .collect();
+    let prior_steps: Vec<OrchestrationStepResult> = stored
+        .plan
+        .steps
+        .into_iter()
+        .filter(|s| completed_ids.contains(&s.id))
+        .collect();
+
+    let mut specs = scheduler::parse_plan(&stored.plan.description, &stored.default_goblin)?;
+    apply_auto_routing(&mut specs, &stored.default_goblin).await;
+    prioritize_by_cost(&mut specs);
+    specs.retain(|s| !completed_ids.contains(&s.id));
+    for spec in specs.iter_mut() {
+        spec.depends_on.retain(|d| !completed_ids.contains(d));
     }
 
-    let mut plan = OrchestrationPlanResult {
-        id: format!("plan_{}", now),
-        description: text.to_string(),
-        steps: steps.clone(),
-        created_at: now,
-        status: "pending".to_string(),
+    run_plan(
+        app,
+        stored.plan.id,
+        stored.plan.description,
+        stored.default_goblin,
+        stored.plan.created_at,
+        specs,
+        prior_steps,
+        None,
+        None,
+        None,
+        DEFAULT_MAX_CONCURRENCY,
+        progress,
+        |_| {},
+    )
+    .await
+}
+
+/// Shared core behind a fresh run (`execute_orchestration_core`, empty
+/// `prior_steps`) and a resumed one (`resume_orchestration_impl`,
+/// `prior_steps` carrying the steps that already completed before the
+/// restart). Writes the plan's stable state through to the durable store as
+/// it progresses: an initial "running" row before `scheduler::run` starts,
+/// then one write per step as it finishes, so a second restart mid-run loses
+/// at most the currently in-flight step instead of the whole plan.
+#[allow(clippy::too_many_arguments)]
+async fn run_plan(
+    app: tauri::AppHandle,
+    plan_id: String,
+    description: String,
+    default_goblin_id: String,
+    created_at: u64,
+    specs: Vec<scheduler::StepSpec>,
+    prior_steps: Vec<OrchestrationStepResult>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: usize,
+    progress: Arc<ProgressRegistry>,
+    mut on_step: impl FnMut(OrchestrationStepResult) + Send + 'static,
+) -> GoblinResult<JsonValue> {
+    let running_plan = OrchestrationPlanResult {
+        id: plan_id.clone(),
+        description: description.clone(),
+        steps: prior_steps.iter().cloned().chain(pending_steps_from_specs(&specs)).collect(),
+        created_at,
+        status: "running".to_string(),
     };
+    let _ = dbctx::save_orchestration_plan(&running_plan, &default_goblin_id).await;
+
+    let tracker = Arc::new(cost_tracker::CostTracker::new(budget_usd, budget_tokens));
+    let retry_policy = orchestration_error::RetryPolicy::default();
+
+    // Aggregate "N of M subtasks complete" progress for the whole plan,
+    // alongside each subtask's own channel published from `run_task_to_completion`.
+    let total_steps = prior_steps.len() + specs.len();
+    let plan_id_for_progress = plan_id.clone();
+    let plan_progress_tx = progress.register(app.clone(), &plan_id, Progress::new(total_steps)).await;
+    let mut plan_steps_done = prior_steps.len();
+
+    let app_for_executor = app.clone();
+    let app_for_outcome = app.clone();
+    let plan_id_for_executor = plan_id.clone();
+    let plan_id_for_outcome = plan_id.clone();
+    let tracker_for_executor = tracker.clone();
+    let progress_for_executor = progress.clone();
+    let specs_for_outcome = specs.clone();
+    let plan_id_for_write_through = plan_id.clone();
+    let outcomes = scheduler::run(
+        specs.clone(),
+        move |spec, dep_results| {
+            let app = app_for_executor.clone();
+            let tracker = tracker_for_executor.clone();
+            let progress = progress_for_executor.clone();
+            async move {
+                let (step_cost, step_tokens) = estimate_step_cost(&spec.task, None);
+                if !tracker.try_reserve(step_cost, step_tokens) {
+                    return Err(orchestration_error::OrchestrationError::System(cost_tracker::DEFERRED_SENTINEL.to_string()));
+                }
 
-    // Execute steps sequentially and update statuses
-    for step in plan.steps.iter_mut() {
-        step.status = "running".to_string();
-        step.started_at = Some(chrono::Utc::now().timestamp_millis() as u64);
-        // Execute the task via existing IPC implementation
-        match execute_task_impl(app.clone(), &step.goblin, &step.task, None).await {
-            Ok(task_id) => {
-                step.status = "completed".to_string();
-                step.result = Some(json!({ "taskId": task_id }));
-                step.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
+                let task = splice_dependency_results(&spec.task, &dep_results);
+                // Await the step's actual completion (not just dispatch) so
+                // its real result - not an opaque task id - is what a
+                // dependent step's `[stepN]` reference resolves to, and so
+                // the step isn't marked `Completed` until the work is done.
+                execute_task_and_await(app, &spec.goblin, &task, None, progress)
+                    .await
+                    .map_err(orchestration_error::OrchestrationError::from)
             }
-            Err(e) => {
-                step.status = "failed".to_string();
-                step.result = Some(json!({ "error": e }));
-                step.completed_at = Some(chrono::Utc::now().timestamp_millis() as u64);
-                // Continue execution - or break? For now, continue.
+        },
+        retry_policy,
+        abort_on_failure.unwrap_or(false),
+        max_concurrency,
+        move |step_id, state| {
+            // A raw `Failed` transition might actually be a budget-refused
+            // admission that `on_outcome` below remaps to `Deferred` once it
+            // can inspect the outcome's result - this closure only gets the
+            // bare state, not the result, so it can't tell which. Skip the
+            // emit here and let `on_outcome` send the (possibly remapped)
+            // one instead, so the UI never observes the uncorrected "failed".
+            if matches!(state, scheduler::StepState::Failed) {
+                return;
             }
+            let _ = app.emit("task-stream", json!({
+                "planId": plan_id_for_executor,
+                "stepId": step_id,
+                "state": state,
+            }));
+        },
+        move |outcome| {
+            if !matches!(outcome.state, scheduler::StepState::Completed | scheduler::StepState::Failed) {
+                return;
+            }
+
+            plan_steps_done += 1;
+            let _ = plan_progress_tx.send(Progress {
+                step: plan_steps_done,
+                total_steps,
+                bytes_or_tokens_done: 0,
+                message: format!("{} of {} subtasks complete", plan_steps_done, total_steps),
+            });
+
+            let Some(spec) = specs_for_outcome.iter().find(|s| s.id == outcome.id) else {
+                return;
+            };
+
+            let deferred = outcome.state == scheduler::StepState::Failed
+                && outcome.result.get("error").and_then(|v| v.get("detail")).and_then(|v| v.as_str()) == Some(cost_tracker::DEFERRED_SENTINEL);
+
+            if outcome.state == scheduler::StepState::Failed {
+                let _ = app_for_outcome.emit("task-stream", json!({
+                    "planId": plan_id_for_outcome,
+                    "stepId": outcome.id,
+                    "state": if deferred { scheduler::StepState::Deferred } else { outcome.state },
+                }));
+            }
+
+            let step = OrchestrationStepResult {
+                id: spec.id.clone(),
+                name: spec.name.clone(),
+                goblin: spec.goblin.clone(),
+                task: spec.task.clone(),
+                depends_on: spec.depends_on.clone(),
+                state: if deferred { scheduler::StepState::Deferred } else { outcome.state },
+                result: Some(if deferred {
+                    json!({ "reason": "would exceed plan budget" })
+                } else {
+                    outcome.result.clone()
+                }),
+                started_at: Some(outcome.started_at),
+                completed_at: Some(outcome.completed_at),
+                attempts: outcome.attempts,
+            };
+
+            let plan_id_for_write_through = plan_id_for_write_through.clone();
+            let step_for_write_through = step.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = dbctx::save_plan_step(&plan_id_for_write_through, &step_for_write_through).await;
+            });
+
+            on_step(step);
+        },
+    ).await;
+
+    let mut steps_by_id: HashMap<String, OrchestrationStepResult> = pending_steps_from_specs(&specs)
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect();
+
+    for outcome in outcomes {
+        if let Some(step) = steps_by_id.get_mut(&outcome.id) {
+            // A step whose execute closure refused admission comes back as
+            // `Failed` with the budget sentinel as its `System` error -
+            // recast it as `Deferred` so the UI can tell "never got the
+            // chance to spend" apart from an actual task failure.
+            let deferred = outcome.state == scheduler::StepState::Failed
+                && outcome.result.get("error").and_then(|v| v.get("detail")).and_then(|v| v.as_str()) == Some(cost_tracker::DEFERRED_SENTINEL);
+
+            step.state = if deferred { scheduler::StepState::Deferred } else { outcome.state };
+            step.result = Some(if deferred {
+                json!({ "reason": "would exceed plan budget" })
+            } else {
+                outcome.result
+            });
+            step.started_at = Some(outcome.started_at);
+            step.completed_at = Some(outcome.completed_at);
+            step.attempts = outcome.attempts;
         }
     }
 
-    plan.status = if plan.steps.iter().any(|s| s.status == "failed") { "failed".to_string() } else { "completed".to_string() };
+    let mut steps: Vec<OrchestrationStepResult> = prior_steps;
+    steps.extend(specs.iter().filter_map(|s| steps_by_id.remove(&s.id)));
+    let status = if steps.iter().any(|s| s.state == scheduler::StepState::Failed) {
+        "failed".to_string()
+    } else {
+        "completed".to_string()
+    };
 
-    Ok(json!(plan))
+    let admitted = steps.iter().filter(|s| s.state == scheduler::StepState::Completed).count();
+    let deferred = steps.iter().filter(|s| s.state == scheduler::StepState::Deferred).count();
+
+    let plan = OrchestrationPlanResult {
+        id: plan_id,
+        description,
+        steps,
+        created_at,
+        status,
+    };
+
+    // Write the plan and its steps through to the durable store so it
+    // survives a restart.
+    let _ = dbctx::save_orchestration_plan(&plan, &default_goblin_id).await;
+    progress.unregister(&plan_id_for_progress).await;
+
+    let mut plan_json = json!(plan);
+    if let Some(obj) = plan_json.as_object_mut() {
+        obj.insert("metrics".to_string(), json!({
+            "admitted": admitted,
+            "deferred": deferred,
+            "totalSpent": tracker.spent_usd(),
+            "budgetUsd": budget_usd,
+            "budgetTokens": budget_tokens,
+        }));
+    }
+
+    Ok(plan_json)
+}
+
+/// List previously run/in-progress orchestration plans, most recent first.
+pub async fn list_plans_impl(limit: Option<usize>) -> GoblinResult<Vec<OrchestrationPlanResult>> {
+    let plans = dbctx::list_plans(limit).await?;
+    Ok(plans.into_iter().map(|stored| stored.plan).collect())
+}
+
+/// Fetch a single orchestration plan (with all its steps) by id.
+pub async fn get_plan_impl(plan_id: &str) -> GoblinResult<Option<OrchestrationPlanResult>> {
+    let stored = dbctx::load_plan(plan_id).await?;
+    Ok(stored.map(|s| s.plan))
+}
+
+/// Log (but don't auto-resume) any plan left in a non-terminal state by a
+/// previous run, so a crash mid-plan isn't silently lost - the user decides
+/// whether to call `resume_orchestration_impl` for each one.
+pub async fn log_incomplete_plans_on_startup() -> GoblinResult<()> {
+    let incomplete = dbctx::list_incomplete_plans().await?;
+    if !incomplete.is_empty() {
+        println!("Found {} incomplete orchestration plan(s) from a previous run:", incomplete.len());
+        for stored in &incomplete {
+            println!("  {} ({}) - status: {}", stored.plan.id, stored.plan.description, stored.plan.status);
+        }
+    }
+    Ok(())
+}
+
+/// Historical cost per provider for tasks started within `[since, until]`
+/// (unix millis). Either bound may be omitted to leave that side open.
+pub async fn get_cost_history_impl(since: Option<u64>, until: Option<u64>) -> GoblinResult<JsonValue> {
+    let totals = dbctx::cost_by_provider(since, until).await?;
+    Ok(json!(totals))
+}
+
+/// Aggregate recorded task cost into `total_cost`/`cost_by_provider`/`cost_by_model`,
+/// optionally scoped to one goblin and/or a `[since, until]` window so the
+/// frontend can render spend over the last hour/day.
+pub async fn get_cost_summary_impl(goblin_id: Option<String>, since: Option<u64>, until: Option<u64>) -> GoblinResult<JsonValue> {
+    let (total_cost, cost_by_provider, cost_by_model) = dbctx::cost_summary(goblin_id.as_deref(), since, until).await?;
+    Ok(json!({
+        "total_cost": total_cost,
+        "cost_by_provider": cost_by_provider,
+        "cost_by_model": cost_by_model
+    }))
 }
 
 /// Parse orchestration text into an OrchestrationPlanResult JSON without executing.
 /// This is intentionally similar to `execute_orchestration_impl` but does not start
 /// any tasks â€” it only returns the parsed plan so the UI can display a preview.
-pub async fn parse_orchestration_impl(text: &str, default_goblin: Option<String>) -> Result<JsonValue, String> {
+pub async fn parse_orchestration_impl(text: &str, default_goblin: Option<String>) -> GoblinResult<JsonValue> {
     let now = chrono::Utc::now().timestamp_millis() as u64;
     let default_goblin_id = default_goblin.unwrap_or_else(|| "websmith".to_string());
 
-    let tokens: Vec<&str> = text.split("THEN").map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-    let mut steps: Vec<OrchestrationStepResult> = vec![];
-    for (idx, token) in tokens.iter().enumerate() {
-        let (goblin, task) = if let Some(pos) = token.find(":") {
-            (
-                token[..pos].trim().to_string(),
-                token[pos + 1..].trim().to_string(),
-            )
-        } else {
-            (default_goblin_id.clone(), token.to_string())
-        };
-
-        let id = format!("plan_step_{}_{}", now, idx);
-        steps.push(OrchestrationStepResult {
-            id: id.clone(),
-            goblin: goblin.clone(),
-            task: task.clone(),
-            status: "pending".to_string(),
-            result: None,
-            started_at: None,
-            completed_at: None,
-        });
-    }
-
+    let mut specs = scheduler::parse_plan(text, &default_goblin_id)?;
+    apply_auto_routing(&mut specs, &default_goblin_id).await;
     let plan = OrchestrationPlanResult {
         id: format!("plan_{}", now),
         description: text.to_string(),
-        steps: steps.clone(),
+        steps: pending_steps_from_specs(&specs),
         created_at: now,
         status: "pending".to_string(),
     };
@@ -740,24 +1507,24 @@ pub async fn parse_orchestration_impl(text: &str, default_goblin: Option<String>
 }
 
 /// Estimate cost for orchestration execution without actually running it
-pub async fn estimate_cost_impl(orchestration_text: &str, code_input: Option<&str>, provider: Option<&str>) -> Result<JsonValue, String> {
+pub async fn estimate_cost_impl(orchestration_text: &str, code_input: Option<&str>, provider: Option<&str>) -> GoblinResult<JsonValue> {
     // Parse the orchestration to get steps
     let plan_result = parse_orchestration_impl(orchestration_text, Some("code-writer".to_string())).await?;
     let plan: OrchestrationPlanResult = serde_json::from_value(plan_result)
-        .map_err(|e| format!("Failed to parse plan: {}", e))?;
+        .map_err(|e| GoblinError::Io(format!("Failed to parse plan: {}", e)))?;
 
     let mut total_cost = 0.0;
     let mut step_costs = Vec::new();
 
     // Estimate cost for each step
+    let provider_name = provider.unwrap_or("openai");
     for step in &plan.steps {
         // Estimate tokens based on task + code input
-        let task_tokens = estimate_tokens_from_text(&step.task);
-        let code_tokens = code_input.map(|c| estimate_tokens_from_text(c)).unwrap_or(0);
+        let task_tokens = tokenizer::count_tokens(provider_name, None, &step.task);
+        let code_tokens = code_input.map(|c| tokenizer::count_tokens(provider_name, None, c)).unwrap_or(0);
         let total_tokens = task_tokens + code_tokens;
 
         // Get cost per token for the provider
-        let provider_name = provider.unwrap_or("openai");
         let cost_per_token = cost_estimator::cost_per_token(provider_name, None);
 
         // Estimate output tokens (assume 2x input for most tasks)
@@ -783,9 +1550,10 @@ pub async fn estimate_cost_impl(orchestration_text: &str, code_input: Option<&st
     }))
 }
 
-// Simple token estimation (rough approximation)
-fn estimate_tokens_from_text(text: &str) -> usize {
-    // Rough estimate: ~4 characters per token for English text
-    let char_count = text.chars().count();
-    ((char_count as f64) / 4.0).ceil() as usize
+/// Replay a benchmark workload file against the mock goblin backend and
+/// return the aggregate report, optionally POSTing it to `post_url` first.
+/// See `benchmark.rs` for the scenario/report shapes this returns as JSON.
+pub async fn run_benchmark_impl(workload_path: &str, post_url: Option<String>) -> GoblinResult<JsonValue> {
+    let report = benchmark::run_and_report_impl(workload_path, post_url).await?;
+    Ok(json!(report))
 }
@@ -3,74 +3,114 @@ use std::sync::Arc;
 // Expose IPC commands for the frontend. Keep these small and forward to
 // the goblin_runtime helper implementations.
 use crate::goblin_runtime;
+use crate::goblin_runtime::error::GoblinResult;
 use crate::GoblinRuntimeManager;
 
 #[tauri::command]
-pub async fn get_goblins(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>) -> Result<Vec<String>, String> {
+pub async fn get_goblins(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>) -> GoblinResult<Vec<String>> {
     goblin_runtime::list_goblins_impl().await
 }
 
 #[tauri::command]
-pub async fn get_stats(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String) -> Result<goblin_runtime::GoblinStats, String> {
+pub async fn get_stats(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String) -> GoblinResult<goblin_runtime::GoblinStats> {
     goblin_runtime::get_goblin_stats_impl(&goblin_id).await
 }
 
 #[tauri::command]
-pub async fn get_history(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String, limit: Option<usize>) -> Result<Vec<goblin_runtime::HistoryEntry>, String> {
+pub async fn get_history(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String, limit: Option<usize>) -> GoblinResult<Vec<goblin_runtime::HistoryEntry>> {
     goblin_runtime::get_history_impl(&goblin_id, limit).await
 }
 
 #[tauri::command]
-pub async fn get_providers(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>) -> Result<Vec<String>, String> {
+pub async fn get_providers(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>) -> GoblinResult<Vec<String>> {
     goblin_runtime::get_providers_impl().await
 }
 
 #[tauri::command]
-pub async fn get_provider_models(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String) -> Result<Vec<String>, String> {
+pub async fn get_provider_models(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String) -> GoblinResult<Vec<String>> {
     goblin_runtime::get_provider_models_impl(&provider).await
 }
 
 #[tauri::command]
-pub async fn get_cost_summary(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>) -> Result<serde_json::Value, String> {
-    // TODO: implement cost summary
-    Ok(serde_json::json!({
-        "total_cost": 0.0,
-        "cost_by_provider": {},
-        "cost_by_model": {}
-    }))
+pub async fn get_cost_summary(
+    _mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>,
+    goblin_id: Option<String>,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::get_cost_summary_impl(goblin_id, since, until).await
 }
 
 #[tauri::command]
-pub async fn parse_orchestration(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, text: String, default_goblin: Option<String>) -> Result<serde_json::Value, String> {
+pub async fn get_cost_history(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, since: Option<u64>, until: Option<u64>) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::get_cost_history_impl(since, until).await
+}
+
+#[tauri::command]
+pub async fn parse_orchestration(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, text: String, default_goblin: Option<String>) -> GoblinResult<serde_json::Value> {
     goblin_runtime::parse_orchestration_impl(&text, default_goblin).await
 }
 
 #[tauri::command]
-pub async fn execute_orchestration(app: tauri::AppHandle, _mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, text: String, default_goblin: Option<String>) -> Result<serde_json::Value, String> {
-    goblin_runtime::execute_orchestration_impl(app, &text, default_goblin).await
+pub async fn execute_orchestration(
+    app: tauri::AppHandle,
+    mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>,
+    text: String,
+    default_goblin: Option<String>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: Option<usize>,
+) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::execute_orchestration_impl(app, &text, default_goblin, budget_usd, budget_tokens, abort_on_failure, max_concurrency, mgr.progress.clone()).await
+}
+
+#[tauri::command]
+pub async fn list_orchestration_plans(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, limit: Option<usize>) -> GoblinResult<Vec<goblin_runtime::OrchestrationPlanResult>> {
+    goblin_runtime::list_plans_impl(limit).await
+}
+
+#[tauri::command]
+pub async fn get_orchestration_plan(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, plan_id: String) -> GoblinResult<Option<goblin_runtime::OrchestrationPlanResult>> {
+    goblin_runtime::get_plan_impl(&plan_id).await
 }
 
 #[tauri::command]
-pub async fn store_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String, _key: String) -> Result<(), String> {
+pub async fn resume_orchestration(app: tauri::AppHandle, mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, plan_id: String) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::resume_orchestration_impl(app, &plan_id, mgr.progress.clone()).await
+}
+
+#[tauri::command]
+pub async fn store_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String, _key: String) -> GoblinResult<()> {
     goblin_runtime::store_api_key_impl(&provider, &_key).await
 }
 
 #[tauri::command]
-pub async fn get_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, _provider: String) -> Result<Option<String>, String> {
+pub async fn get_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, _provider: String) -> GoblinResult<Option<String>> {
     goblin_runtime::get_api_key_impl(&_provider).await
 }
 
 #[tauri::command]
-pub async fn clear_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String) -> Result<(), String> {
+pub async fn clear_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String) -> GoblinResult<()> {
     goblin_runtime::clear_api_key_impl(&provider).await
 }
 
 #[tauri::command]
-pub async fn set_provider_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String, _key: String) -> Result<(), String> {
+pub async fn set_provider_api_key(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, provider: String, _key: String) -> GoblinResult<()> {
     goblin_runtime::set_provider_api_key_impl(&provider, &_key).await
 }
 
 #[tauri::command]
-pub async fn execute_task(app: tauri::AppHandle, _mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String, task: String, args: Option<serde_json::Value>) -> Result<String, String> {
-    goblin_runtime::execute_task_impl(app, &goblin_id, &task, args).await
+pub async fn execute_task(app: tauri::AppHandle, mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String, task: String, args: Option<serde_json::Value>) -> GoblinResult<String> {
+    goblin_runtime::execute_task_impl(app, &goblin_id, &task, args, mgr.progress.clone()).await
+}
+
+#[tauri::command]
+pub async fn run_benchmark(_mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, workload_path: String, post_url: Option<String>) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::run_benchmark_impl(&workload_path, post_url).await
+}
+
+#[tauri::command]
+pub async fn run_tome(app: tauri::AppHandle, mgr: tauri::State<'_, Arc<GoblinRuntimeManager>>, goblin_id: String) -> GoblinResult<serde_json::Value> {
+    goblin_runtime::run_tome_impl(app, &goblin_id, mgr.progress.clone()).await
 }
@@ -3,9 +3,10 @@
 
 mod goblin_runtime;
 mod ipc;
+mod webhook;
 
-use goblin_runtime::{start_runtime, stop_runtime, status, send_event};
-use ipc::{get_goblins, get_providers, get_provider_models, get_stats, get_history, get_cost_summary, parse_orchestration, execute_orchestration, store_api_key, get_api_key, clear_api_key, set_provider_api_key, execute_task};
+use goblin_runtime::{start_runtime, stop_runtime, status, send_event, set_cost_budget, get_cost_usage};
+use ipc::{get_goblins, get_providers, get_provider_models, get_stats, get_history, get_cost_summary, get_cost_history, parse_orchestration, execute_orchestration, list_orchestration_plans, get_orchestration_plan, resume_orchestration, store_api_key, get_api_key, clear_api_key, set_provider_api_key, execute_task, run_benchmark, run_tome};
 use std::sync::Arc;
 
 /// A lightweight manager that will own runtime-related resources.
@@ -16,6 +17,11 @@ pub struct GoblinRuntimeManager {
     // placeholder for real runtime resources
     pub name: String,
     pub child_process: Option<std::sync::Arc<std::sync::Mutex<Option<tokio::process::Child>>>>,
+    /// Per-task/plan progress channels shared by every IPC command and the
+    /// webhook server, so progress published from `execute_task_impl`/
+    /// `run_plan` reaches the frontend regardless of which entry point
+    /// kicked the work off.
+    pub progress: Arc<goblin_runtime::progress::ProgressRegistry>,
 }
 
 impl GoblinRuntimeManager {
@@ -23,6 +29,7 @@ impl GoblinRuntimeManager {
         GoblinRuntimeManager {
             name: "goblin-runtime-manager".into(),
             child_process: None,
+            progress: Arc::new(goblin_runtime::progress::ProgressRegistry::new()),
         }
     }
 }
@@ -42,6 +49,21 @@ fn main() {
                 .min_inner_size(800.0, 600.0)
                 .center()
                 .build()?;
+
+            let data_dir = app.path_resolver()
+                .app_data_dir()
+                .unwrap_or_else(|| std::path::PathBuf::from("."));
+            let db_path = data_dir.join("goblin.db");
+            tauri::async_runtime::block_on(goblin_runtime::dbctx::init(db_path))
+                .map_err(|e| format!("Failed to initialize database: {}", e))?;
+            tauri::async_runtime::block_on(goblin_runtime::seed_session_cost_usage())
+                .map_err(|e| format!("Failed to seed session cost usage: {}", e))?;
+            tauri::async_runtime::block_on(goblin_runtime::log_incomplete_plans_on_startup())
+                .map_err(|e| format!("Failed to check for incomplete orchestration plans: {}", e))?;
+
+            webhook::spawn_webhook_server(app.handle());
+            goblin_runtime::config_watch::spawn_config_watcher(app.handle());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -50,6 +72,8 @@ fn main() {
             stop_runtime,
             send_event,
             status,
+            set_cost_budget,
+            get_cost_usage,
             // new IPC commands
             get_goblins,
             get_providers,
@@ -57,13 +81,19 @@ fn main() {
             get_stats,
             get_history,
             get_cost_summary,
+            get_cost_history,
             parse_orchestration,
             store_api_key,
             get_api_key,
             clear_api_key,
             set_provider_api_key,
             execute_orchestration,
-            execute_task
+            list_orchestration_plans,
+            get_orchestration_plan,
+            resume_orchestration,
+            execute_task,
+            run_benchmark,
+            run_tome
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
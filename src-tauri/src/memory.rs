@@ -1,23 +1,53 @@
+// Goblin conversation/audit history. Durably backed by `dbctx`'s
+// `history_entries` table so it survives a restart; the in-memory map below
+// is only a last-resort fallback for callers where the database hasn't been
+// initialized (e.g. tests), the same fallback `get_history_impl` already
+// leans on when the live goblin-runtime is unreachable.
+
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use tokio::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::dbctx;
+
 lazy_static! {
     static ref MEMORY_STORE: Mutex<HashMap<String, Vec<(u64, String)>>> = Mutex::new(HashMap::new());
 }
 
+/// How much history to keep per goblin, read fresh on every insert so it can
+/// be tuned without a restart. `GOBLIN_HISTORY_MAX_ROWS` caps row count;
+/// `GOBLIN_HISTORY_MAX_AGE_MS` caps age; either can be left unset to leave
+/// that dimension unbounded.
+fn retention_policy() -> (Option<usize>, Option<u64>) {
+    let max_rows = std::env::var("GOBLIN_HISTORY_MAX_ROWS").ok().and_then(|v| v.parse().ok());
+    let max_age_ms = std::env::var("GOBLIN_HISTORY_MAX_AGE_MS").ok().and_then(|v| v.parse().ok());
+    (max_rows, max_age_ms)
+}
+
 pub async fn add_history_entry(goblin_id: &str, message: &str) {
+    let ts = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+
+    if dbctx::record_history_entry(goblin_id, ts, message).await.is_ok() {
+        let (max_rows, max_age_ms) = retention_policy();
+        let _ = dbctx::enforce_history_retention(goblin_id, max_rows, max_age_ms, ts).await;
+        return;
+    }
+
+    // Database not initialized (e.g. in tests) - last-resort in-memory store.
     let mut store = MEMORY_STORE.lock().await;
     let bucket = store.entry(goblin_id.to_string()).or_insert_with(Vec::new);
-    let ts = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
     bucket.push((ts, message.to_string()));
 }
 
 pub async fn get_history(goblin_id: &str, limit: Option<usize>) -> Vec<(u64, String)> {
+    if let Ok(entries) = dbctx::get_history_entries(goblin_id, limit).await {
+        return entries;
+    }
+
+    // Database not initialized (e.g. in tests) - last-resort in-memory store.
     let store = MEMORY_STORE.lock().await;
-    let entries = store.get(goblin_id).cloned().unwrap_or_default();
-    let mut entries = entries;
+    let mut entries = store.get(goblin_id).cloned().unwrap_or_default();
     entries.reverse(); // newest first
     if let Some(l) = limit {
         entries.truncate(l);
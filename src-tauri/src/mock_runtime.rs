@@ -0,0 +1,51 @@
+// Offline/deterministic mode for exercising `execute_task`,
+// `execute_orchestration`, and the provider/model listing commands without a
+// real goblin-runtime child process, API keys, or network access - unblocks
+// CI and local demos. Toggled by `GOBLIN_MOCK_MODE=1`; `GOBLIN_MOCK_LATENCY_MS`
+// and `GOBLIN_MOCK_FAIL_RATE` inject synthetic latency/failures so the
+// progress and cancel paths can be exercised the same way they would be
+// against a real, slower, occasionally-flaky provider.
+
+use rand::Rng;
+use serde_json::json;
+
+/// Provider id `get_providers_impl`/`get_provider_models_impl` surface when
+/// mock mode is on, so the UI has something to route requests to.
+pub const MOCK_PROVIDER: &str = "mock";
+pub const MOCK_MODEL: &str = "mock-instant";
+
+pub fn enabled() -> bool {
+    std::env::var("GOBLIN_MOCK_MODE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Synthetic per-chunk delay, standing in for real provider latency.
+/// Defaults to near-instant so CI runs stay fast unless a test opts into
+/// slower, more realistic timing.
+pub fn latency() -> std::time::Duration {
+    let ms = std::env::var("GOBLIN_MOCK_LATENCY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(50);
+    std::time::Duration::from_millis(ms)
+}
+
+fn fail_rate() -> f64 {
+    std::env::var("GOBLIN_MOCK_FAIL_RATE").ok().and_then(|v| v.parse().ok()).unwrap_or(0.0)
+}
+
+/// Roll the dice against `GOBLIN_MOCK_FAIL_RATE`, so a demo/CI run can
+/// exercise the cancel/error paths without a real flaky provider.
+pub fn should_fail() -> bool {
+    let rate = fail_rate();
+    rate > 0.0 && rand::thread_rng().gen::<f64>() < rate
+}
+
+/// A deterministic canned reply for `task`, standing in for the real
+/// goblin-runtime response `execute_task_impl` would otherwise wait on. The
+/// chunk count is fixed rather than derived from `task`, so a caller can
+/// rely on how many `task-stream`/progress events to expect in a test.
+pub fn canned_response(goblin_id: &str, task: &str) -> serde_json::Value {
+    json!({
+        "taskId": format!("mock_{}_{}", goblin_id, task.len()),
+        "chunks": ["mock chunk 1", "mock chunk 2", "mock chunk 3"],
+    })
+}
@@ -0,0 +1,117 @@
+// Typed failures from a single orchestration step's execute closure.
+// Replaces the opaque `String` error `scheduler::run` used to carry -
+// classified into retryable (transient, worth another attempt) vs terminal
+// (no point trying again), and serializes cleanly into a step's `result` so
+// the frontend can render something better than a raw message.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::error::GoblinError;
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail")]
+pub enum OrchestrationError {
+    /// Catch-all for a failure with no closer classification - whatever the
+    /// runtime/provider reported, as-is. Treated as terminal: we have no
+    /// basis to assume retrying would help.
+    #[error("{0}")]
+    Raw(String),
+
+    /// The runtime process or a provider's connection dropped mid-request -
+    /// usually transient.
+    #[error("connection error: {0}")]
+    ConnectionError(String),
+
+    /// The provider rejected the request for being too fast, not too wrong.
+    #[error("provider rate limited the request")]
+    ProviderRateLimited { retry_after_ms: Option<u64> },
+
+    /// The step didn't hear back from the runtime in time.
+    #[error("step timed out")]
+    Timeout,
+
+    /// A local/system failure (spawn, keyring, runtime not running) or an
+    /// error reply from the runtime other than rate limiting - retrying won't
+    /// change the outcome.
+    #[error("system error: {0}")]
+    System(String),
+}
+
+impl OrchestrationError {
+    /// Whether this failure is worth another attempt. Connection hiccups,
+    /// rate limiting, and timeouts are transient; an unclassified `Raw`
+    /// message or an explicit `System` error are terminal.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OrchestrationError::ConnectionError(_)
+                | OrchestrationError::ProviderRateLimited { .. }
+                | OrchestrationError::Timeout
+        )
+    }
+}
+
+impl From<String> for OrchestrationError {
+    fn from(message: String) -> Self {
+        OrchestrationError::Raw(message)
+    }
+}
+
+/// Map the runtime's own error taxonomy onto the narrower retryable/terminal
+/// split a scheduled step cares about. `Protocol` messages are pattern
+/// matched for the one case (rate limiting) the runtime doesn't surface as
+/// its own variant - everything else we can actually distinguish structurally.
+impl From<GoblinError> for OrchestrationError {
+    fn from(e: GoblinError) -> Self {
+        let message = e.to_string();
+        match e {
+            GoblinError::Timeout => OrchestrationError::Timeout,
+            GoblinError::Io(message) => OrchestrationError::ConnectionError(message),
+            GoblinError::Protocol { message, .. } if message.to_lowercase().contains("rate limit") => {
+                OrchestrationError::ProviderRateLimited { retry_after_ms: None }
+            }
+            GoblinError::Protocol { message, .. } => OrchestrationError::System(message),
+            GoblinError::RuntimeNotRunning => OrchestrationError::System(message),
+            GoblinError::Spawn(message) => OrchestrationError::System(message),
+            GoblinError::Keyring(message) => OrchestrationError::System(message),
+            GoblinError::UnexpectedResponse(value) => OrchestrationError::Raw(value.to_string()),
+        }
+    }
+}
+
+/// How `scheduler::run` retries a step whose execute closure returned a
+/// retryable `OrchestrationError`.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Total attempts allowed, including the first. `1` means no retries.
+    pub max_attempts: u32,
+    pub base_delay: std::time::Duration,
+    pub max_delay: Option<std::time::Duration>,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: std::time::Duration, max_delay: Option<std::time::Duration>) -> Self {
+        RetryPolicy { max_attempts, base_delay, max_delay }
+    }
+
+    /// Exponential backoff, doubling per attempt, with +/-20% jitter so a
+    /// burst of retrying steps doesn't all wake up and hit the provider at
+    /// once. `attempt` is the attempt number that just failed (1-based).
+    pub fn delay_for_attempt(&self, attempt: u32) -> std::time::Duration {
+        let exponent = attempt.saturating_sub(1).min(10);
+        let mut delay = self.base_delay * 2u32.pow(exponent);
+        if let Some(cap) = self.max_delay {
+            delay = delay.min(cap);
+        }
+        let jitter = rand::thread_rng().gen_range(0.8..1.2);
+        delay.mul_f64(jitter)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(3, std::time::Duration::from_millis(250), Some(std::time::Duration::from_secs(10)))
+    }
+}
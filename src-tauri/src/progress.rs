@@ -0,0 +1,70 @@
+// Progress reporting for long-running tasks and orchestration runs. Each
+// in-flight task/plan gets its own `watch::Sender<Progress>`, registered
+// here under its task/plan id; `register` also spawns a background task
+// that subscribes to the channel and re-emits every change to the frontend
+// as `goblin://progress/{id}`, so callers only need to `send` updates
+// through the returned sender.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::sync::{watch, Mutex};
+
+/// A snapshot of how far a task or orchestration plan has gotten.
+/// `bytes_or_tokens_done` is whichever unit makes sense for the caller
+/// (streamed chunk count, token count, etc) - the frontend only needs
+/// `step`/`total_steps` to draw a progress bar, and treats the rest as
+/// supplementary detail.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Progress {
+    pub step: usize,
+    pub total_steps: usize,
+    pub bytes_or_tokens_done: usize,
+    pub message: String,
+}
+
+impl Progress {
+    pub fn new(total_steps: usize) -> Self {
+        Progress { step: 0, total_steps, bytes_or_tokens_done: 0, message: String::new() }
+    }
+}
+
+/// Per-task/plan-id table of progress channels, owned by `GoblinRuntimeManager`.
+#[derive(Default)]
+pub struct ProgressRegistry {
+    channels: Mutex<HashMap<String, watch::Sender<Progress>>>,
+}
+
+impl ProgressRegistry {
+    pub fn new() -> Self {
+        ProgressRegistry { channels: Mutex::new(HashMap::new()) }
+    }
+
+    /// Register a fresh progress channel for `id`, spawn a background
+    /// forwarder that re-emits every update as `goblin://progress/{id}`, and
+    /// return the sender side for the caller to publish updates through.
+    pub async fn register(&self, app: tauri::AppHandle, id: &str, initial: Progress) -> watch::Sender<Progress> {
+        let (tx, mut rx) = watch::channel(initial);
+        self.channels.lock().await.insert(id.to_string(), tx.clone());
+
+        let event = format!("goblin://progress/{}", id);
+        tokio::spawn(async move {
+            // `changed()` only errs once every sender for this channel -
+            // including the one the registry itself holds - is dropped,
+            // which `unregister` takes care of once the task finishes.
+            while rx.changed().await.is_ok() {
+                let progress = rx.borrow_and_update().clone();
+                let _ = app.emit(&event, &progress);
+            }
+        });
+
+        tx
+    }
+
+    /// Drop the channel for a finished task/plan so the registry doesn't
+    /// grow unbounded across a long-running session.
+    pub async fn unregister(&self, id: &str) {
+        self.channels.lock().await.remove(id);
+    }
+}
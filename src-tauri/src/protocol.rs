@@ -0,0 +1,63 @@
+// Framing for the newline-delimited JSON protocol spoken with the
+// goblin-runtime child process. Every request we send carries an `id`;
+// every reply the runtime sends back echoes that `id` so replies can be
+// matched to the call that produced them even when several calls are
+// in flight at once. Lines that don't carry a recognizable `id` (e.g.
+// the initial `{ready:true}` handshake or ad-hoc log lines) are treated
+// as unsolicited and handed back to the caller for logging instead of
+// being routed to a waiter.
+
+use serde_json::Value as JsonValue;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Allocate a unique, monotonically increasing request id for this process.
+pub fn new_request_id() -> String {
+    let n = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    format!("req_{}", n)
+}
+
+/// Serialize a request envelope as a single newline-terminated JSON line,
+/// ready to be written to the child's stdin.
+pub fn frame_request(id: &str, method: &str, mut fields: JsonValue) -> String {
+    if let JsonValue::Object(ref mut map) = fields {
+        map.insert("id".to_string(), JsonValue::String(id.to_string()));
+        map.insert("method".to_string(), JsonValue::String(method.to_string()));
+    }
+    fields.to_string() + "\n"
+}
+
+/// A single decoded line from the child's stdout.
+pub enum IncomingFrame {
+    /// A reply to a request we issued, matched by `id`.
+    Reply { id: String, result: Result<JsonValue, String> },
+    /// A line that doesn't correlate to an outstanding request (the
+    /// `{ready:true}` handshake, out-of-band logs, etc).
+    Unsolicited(JsonValue),
+}
+
+/// Parse one line of runtime stdout. Returns `None` for blank lines or
+/// lines that aren't valid JSON at all.
+pub fn parse_incoming(line: &str) -> Option<IncomingFrame> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let value: JsonValue = serde_json::from_str(trimmed).ok()?;
+
+    let id = value.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let id = match id {
+        Some(id) => id,
+        None => return Some(IncomingFrame::Unsolicited(value)),
+    };
+
+    if let Some(error) = value.get("error") {
+        let message = error.as_str().unwrap_or("Unknown error").to_string();
+        return Some(IncomingFrame::Reply { id, result: Err(message) });
+    }
+
+    let result = value.get("result").cloned().unwrap_or(value);
+    Some(IncomingFrame::Reply { id, result: Ok(result) })
+}
@@ -0,0 +1,96 @@
+// Pluggable execution backend for a goblin. `execute_task_impl` used to
+// hardwire "ask the real goblin-runtime child process, unless mock mode is
+// on" directly inline; that branch now lives behind this `Runtime` trait
+// instead, so `execute_task_impl`/`tome::run_tome_program` don't need to
+// know which backend answered a call. Beyond the provider call itself,
+// `Runtime` also exposes the host capabilities a goblin's tome program
+// needs while it runs - emitting a live event, recording durable history,
+// and checking/charging a step's cost against the plan's budget - so
+// `tome::run_tome_program` drives a step purely through this trait rather
+// than reaching into `goblin_runtime`'s own globals directly. Only
+// `call_step` actually differs between backends; the rest have a shared
+// default implementation both `LiveRuntime` and `MockRuntime` inherit,
+// with a seam for a future backend (e.g. a test harness that wants to
+// capture events instead of emitting them) to override one in isolation.
+
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+
+use crate::cost_tracker::CostTracker;
+use crate::error::GoblinResult;
+
+#[async_trait]
+pub trait Runtime: Send + Sync {
+    /// Run one step (a goblin + task text, with any previously-resolved
+    /// dependency outputs already spliced into `task`) and return its raw
+    /// result payload, the same shape `send_message_to_runtime("executeTask", ..)`
+    /// already returns.
+    async fn call_step(&self, goblin_id: &str, task: &str, system_prompt: &str, args: Option<JsonValue>) -> GoblinResult<JsonValue>;
+
+    /// Rough cost/token estimate for a step's task text, the same math
+    /// `goblin_runtime::estimate_step_cost` already uses for DAG-plan
+    /// budget admission. `goblin_id` isn't a provider name, so (like every
+    /// other `estimate_step_cost` caller) this doesn't try to derive one
+    /// from it - it estimates against the default provider rate.
+    fn estimate_cost(&self, _goblin_id: &str, task: &str) -> (f64, usize) {
+        super::estimate_step_cost(task, None)
+    }
+
+    /// Check `cost_usd`/`tokens` against `tracker`'s plan-level budget and,
+    /// if admitted, record the spend atomically. Returns `false` (nothing
+    /// recorded) if admitting would cross the budget.
+    fn charge_cost(&self, tracker: &CostTracker, cost_usd: f64, tokens: usize) -> bool {
+        tracker.try_reserve(cost_usd, tokens)
+    }
+
+    /// Emit a live event to the UI.
+    async fn send_event(&self, app: &tauri::AppHandle, event: &str, payload: JsonValue) {
+        use tauri::Emitter;
+        let _ = app.emit(event, payload);
+    }
+
+    /// Append an entry to a goblin's durable, cross-session history.
+    async fn add_history_entry(&self, goblin_id: &str, message: &str) {
+        crate::memory::add_history_entry(goblin_id, message).await;
+    }
+}
+
+/// Talks to the real goblin-runtime child process over stdin/stdout.
+pub struct LiveRuntime;
+
+#[async_trait]
+impl Runtime for LiveRuntime {
+    async fn call_step(&self, goblin_id: &str, task: &str, system_prompt: &str, args: Option<JsonValue>) -> GoblinResult<JsonValue> {
+        super::send_message_to_runtime("executeTask", json!({
+            "task": {
+                "goblin": goblin_id,
+                "task": task,
+                "system_prompt": system_prompt,
+                "context": args
+            }
+        })).await
+    }
+}
+
+/// Returns deterministic canned responses instead of talking to a real
+/// child process - see `mock_runtime`. Host capabilities (events, history,
+/// cost) are unchanged from the live backend: mock mode only fakes the
+/// provider call itself, not the bookkeeping around it.
+pub struct MockRuntime;
+
+#[async_trait]
+impl Runtime for MockRuntime {
+    async fn call_step(&self, goblin_id: &str, task: &str, _system_prompt: &str, _args: Option<JsonValue>) -> GoblinResult<JsonValue> {
+        Ok(crate::mock_runtime::canned_response(goblin_id, task))
+    }
+}
+
+/// Pick the backend for this call the same way `mock_runtime::enabled()`
+/// always has - `GOBLIN_MOCK_MODE=1` routes every step through `MockRuntime`.
+pub fn current() -> Box<dyn Runtime> {
+    if crate::mock_runtime::enabled() {
+        Box::new(MockRuntime)
+    } else {
+        Box::new(LiveRuntime)
+    }
+}
@@ -0,0 +1,471 @@
+// DAG scheduling for orchestration plans.
+//
+// `parse_plan` turns the small orchestration grammar into a graph of
+// `StepSpec`s: `THEN` introduces a barrier (everything after it depends on
+// everything in the stage before it), `AND` groups steps within a stage that
+// may run concurrently, `-> name` names a step's output so later steps can
+// `needs name` it explicitly, and a `[stepN]` reference anywhere in a task's
+// text depends on that step directly (`stepN` is the step's id, assigned in
+// parse order) and has its result spliced into the task text before
+// execution. Dependency edges from `needs`/`[stepN]` aren't restricted to
+// earlier steps, so `parse_plan` runs a topological check after building the
+// graph and rejects the plan with a clear error if it finds a cycle. `run`
+// then drives the (acyclic) graph to completion, launching each step as soon
+// as its dependencies are satisfied (bounded by `max_concurrency` concurrent
+// steps at a time), retrying transient (`OrchestrationError::is_retryable`)
+// failures per the caller's `RetryPolicy`, and skipping anything whose
+// dependency chain failed.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::orchestration_error::{OrchestrationError, RetryPolicy};
+
+/// Lifecycle of a single scheduled step. Mirrors `OrchestrationStepResult`'s
+/// old free-form `status: String` but closes the set so the frontend (and
+/// the scheduler itself) can match on it exhaustively.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepState {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Skipped,
+    AlreadyRunning,
+    /// Admission was refused by a `CostTracker` budget before the step ever
+    /// ran - distinct from `Failed` so the UI can tell "ran and errored"
+    /// apart from "never got the chance to spend".
+    Deferred,
+}
+
+impl StepState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepState::Pending => "pending",
+            StepState::Running => "running",
+            StepState::Completed => "completed",
+            StepState::Failed => "failed",
+            StepState::Skipped => "skipped",
+            StepState::AlreadyRunning => "already_running",
+            StepState::Deferred => "deferred",
+        }
+    }
+}
+
+/// A step parsed out of orchestration text, with its dependencies resolved
+/// to step ids (not names - `parse_plan` does that translation).
+#[derive(Clone, Debug)]
+pub struct StepSpec {
+    pub id: String,
+    pub name: Option<String>,
+    pub goblin: String,
+    pub task: String,
+    pub depends_on: Vec<String>,
+    /// True when the author didn't write an explicit `goblin:` prefix, so
+    /// `goblin` is just `default_goblin` and eligible for embedding-based
+    /// auto-routing (see `embeddings::route_task`).
+    pub auto_route: bool,
+}
+
+/// Parse `THEN`/`AND`/`-> name`/`needs name`/`[stepN]` orchestration text
+/// into a DAG of steps. Steps with no explicit goblin fall back to
+/// `default_goblin`. Runs in two passes: the first assigns every step a
+/// sequential id and records its `-> name`, so a `needs`/`[stepN]` reference
+/// can resolve regardless of whether it points at an earlier or later step;
+/// the second resolves those references into `depends_on` now that every id
+/// and name is known. Fails with a cycle description if the resulting graph
+/// isn't acyclic.
+pub fn parse_plan(text: &str, default_goblin: &str) -> Result<Vec<StepSpec>, String> {
+    let stages: Vec<&str> = text.split("THEN").map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+
+    struct ParsedToken {
+        id: String,
+        goblin: String,
+        task: String,
+        name: Option<String>,
+        needs: Vec<String>,
+        auto_route: bool,
+        stage_idx: usize,
+    }
+
+    let mut tokens: Vec<ParsedToken> = Vec::new();
+    let mut name_to_id: HashMap<String, String> = HashMap::new();
+    let mut next_id = 0usize;
+
+    for (stage_idx, stage) in stages.iter().enumerate() {
+        let branches: Vec<&str> = stage.split("AND").map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        for branch in branches {
+            let id = format!("step{}", next_id);
+            next_id += 1;
+            let (goblin, task, explicit_name, explicit_needs, auto_route) = parse_step_token(branch, default_goblin);
+            if let Some(name) = &explicit_name {
+                name_to_id.insert(name.clone(), id.clone());
+            }
+            tokens.push(ParsedToken { id, goblin, task, name: explicit_name, needs: explicit_needs, auto_route, stage_idx });
+        }
+    }
+
+    let all_ids: HashSet<String> = tokens.iter().map(|t| t.id.clone()).collect();
+    let prev_stage_ids: HashMap<usize, Vec<String>> = {
+        let mut by_stage: HashMap<usize, Vec<String>> = HashMap::new();
+        for token in &tokens {
+            by_stage.entry(token.stage_idx).or_default().push(token.id.clone());
+        }
+        let max_stage = tokens.iter().map(|t| t.stage_idx).max().unwrap_or(0);
+        (0..=max_stage)
+            .map(|stage_idx| (stage_idx, if stage_idx == 0 { Vec::new() } else { by_stage.remove(&(stage_idx - 1)).unwrap_or_default() }))
+            .collect()
+    };
+
+    let mut steps: Vec<StepSpec> = Vec::new();
+    for token in &tokens {
+        let mut depends_on = prev_stage_ids.get(&token.stage_idx).cloned().unwrap_or_default();
+
+        for needed in &token.needs {
+            if let Some(dep_id) = name_to_id.get(needed) {
+                if !depends_on.contains(dep_id) {
+                    depends_on.push(dep_id.clone());
+                }
+            }
+        }
+        for referenced in extract_step_refs(&token.task) {
+            if all_ids.contains(&referenced) && referenced != token.id && !depends_on.contains(&referenced) {
+                depends_on.push(referenced);
+            }
+        }
+
+        steps.push(StepSpec {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            goblin: token.goblin.clone(),
+            task: token.task.clone(),
+            depends_on,
+            auto_route: token.auto_route,
+        });
+    }
+
+    reject_cycles(&steps)?;
+    Ok(steps)
+}
+
+/// Find every `[stepN]` reference in `task` (e.g. `"summarize [step0]"` ->
+/// `["step0"]`), in the order they appear. Doesn't validate that the id
+/// actually exists - the caller filters against the real id set.
+fn extract_step_refs(task: &str) -> Vec<String> {
+    let mut refs = Vec::new();
+    let mut rest = task;
+    while let Some(start) = rest.find("[step") {
+        let after_bracket = &rest[start + 1..];
+        match after_bracket.find(']') {
+            Some(end) => {
+                let candidate = &after_bracket[..end];
+                let digits = &candidate["step".len()..];
+                if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+                    refs.push(candidate.to_string());
+                }
+                rest = &after_bracket[end + 1..];
+            }
+            None => break,
+        }
+    }
+    refs
+}
+
+/// Kahn's algorithm over `depends_on`: repeatedly remove steps with no
+/// unresolved dependency until nothing's left, or nothing can be removed -
+/// whatever remains at that point is part of (or downstream of) a cycle.
+fn reject_cycles(steps: &[StepSpec]) -> Result<(), String> {
+    let mut in_degree: HashMap<String, usize> = steps.iter().map(|s| (s.id.clone(), s.depends_on.len())).collect();
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+    for step in steps {
+        for dep in &step.depends_on {
+            dependents.entry(dep.clone()).or_default().push(step.id.clone());
+        }
+    }
+
+    let mut queue: VecDeque<String> = in_degree.iter().filter(|(_, &deg)| deg == 0).map(|(id, _)| id.clone()).collect();
+    let mut resolved = 0usize;
+
+    while let Some(id) = queue.pop_front() {
+        resolved += 1;
+        if let Some(next) = dependents.get(&id) {
+            for dependent in next {
+                if let Some(deg) = in_degree.get_mut(dependent) {
+                    *deg -= 1;
+                    if *deg == 0 {
+                        queue.push_back(dependent.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    if resolved < steps.len() {
+        let stuck: Vec<&str> = steps
+            .iter()
+            .filter(|s| in_degree.get(&s.id).copied().unwrap_or(0) > 0)
+            .map(|s| s.id.as_str())
+            .collect();
+        return Err(format!("Orchestration plan has a dependency cycle involving: {}", stuck.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Parse one `AND`-separated token: `goblin: task -> name needs other`.
+/// Returns (goblin, task, name, needs, auto_route) - `auto_route` is true
+/// when the token had no explicit `goblin:` prefix, so `goblin` is just
+/// `default_goblin` and is a candidate for embedding-based routing.
+pub(crate) fn parse_step_token(token: &str, default_goblin: &str) -> (String, String, Option<String>, Vec<String>, bool) {
+    let mut rest = token.trim().to_string();
+
+    // Pull off trailing " needs <name>" clauses (single-word names only).
+    let mut needs = Vec::new();
+    loop {
+        match rest.rfind(" needs ") {
+            Some(pos) => {
+                let needed_name = rest[pos + " needs ".len()..].trim().to_string();
+                if needed_name.is_empty() || needed_name.contains(' ') {
+                    break;
+                }
+                needs.push(needed_name);
+                rest = rest[..pos].trim().to_string();
+            }
+            None => break,
+        }
+    }
+    needs.reverse();
+
+    let mut name = None;
+    if let Some(pos) = rest.find("->") {
+        let (head, tail) = rest.split_at(pos);
+        name = Some(tail[2..].trim().to_string());
+        rest = head.trim().to_string();
+    }
+
+    let (goblin, task, auto_route) = if let Some(pos) = rest.find(':') {
+        (rest[..pos].trim().to_string(), rest[pos + 1..].trim().to_string(), false)
+    } else {
+        (default_goblin.to_string(), rest.clone(), true)
+    };
+
+    (goblin, task, name, needs, auto_route)
+}
+
+/// Outcome of driving a single step to completion (or exhaustion of
+/// retries), fed back into the caller's plan-level bookkeeping.
+pub struct StepOutcome {
+    pub id: String,
+    pub state: StepState,
+    pub result: serde_json::Value,
+    pub started_at: u64,
+    pub completed_at: u64,
+    pub attempts: u32,
+    /// Set on a `Failed` outcome whose error was non-retryable, as opposed
+    /// to one that simply exhausted its retries. Only this kind of failure
+    /// can trigger `abort_on_terminal_failure`.
+    pub terminal: bool,
+}
+
+/// Drive `steps` to completion, respecting `depends_on` in-degree: a step
+/// only starts once every dependency has reached `Completed`. If a
+/// dependency ends in `Failed`/`Skipped`, dependents are marked `Skipped`
+/// instead of being run. At most `max_concurrency` steps run at once; the
+/// rest wait their turn even once their dependencies are satisfied. `execute`
+/// is called once per attempt with the step and a map of its already-
+/// completed dependencies' results (keyed by step id), and should return
+/// `Ok(value)` on success or `Err(OrchestrationError)` on failure; retryable
+/// errors are retried per `retry_policy`, terminal ones fail the step
+/// immediately. `on_transition` is invoked with each step's state as it
+/// changes so the caller can stream progress; `on_outcome` is invoked with
+/// the full `StepOutcome` the moment a step reaches `Completed`, `Failed`,
+/// or `Skipped`, for a caller that needs the result payload as it happens
+/// rather than waiting for the whole plan to finish.
+///
+/// When `abort_on_terminal_failure` is set, a terminal (non-retryable)
+/// failure anywhere in the plan stops any further not-yet-started step from
+/// launching - they're marked `Skipped` the same as a failed dependency
+/// would cause. Steps already running are left to finish.
+pub async fn run<F, Fut, T>(
+    steps: Vec<StepSpec>,
+    execute: F,
+    retry_policy: RetryPolicy,
+    abort_on_terminal_failure: bool,
+    max_concurrency: usize,
+    mut on_transition: impl FnMut(&str, StepState),
+    mut on_outcome: impl FnMut(&StepOutcome),
+) -> Vec<StepOutcome>
+where
+    F: Fn(StepSpec, HashMap<String, serde_json::Value>) -> Fut + Clone,
+    Fut: std::future::Future<Output = Result<T, OrchestrationError>> + Send + 'static,
+    T: Into<serde_json::Value> + Send + 'static,
+{
+    let max_concurrency = max_concurrency.max(1);
+    let by_id: HashMap<String, StepSpec> = steps.iter().map(|s| (s.id.clone(), s.clone())).collect();
+    let mut completed: HashSet<String> = HashSet::new();
+    let mut failed_or_skipped: HashSet<String> = HashSet::new();
+    let mut outcomes: HashMap<String, StepOutcome> = HashMap::new();
+    let mut remaining: Vec<String> = steps.iter().map(|s| s.id.clone()).collect();
+    let mut abort_requested = false;
+
+    let mut join_set: JoinSet<StepOutcome> = JoinSet::new();
+    let mut in_flight: HashSet<String> = HashSet::new();
+
+    while !remaining.is_empty() || !in_flight.is_empty() {
+        // Launch every step whose dependencies are all resolved, up to
+        // whatever capacity `max_concurrency` leaves - failure/abort skips
+        // don't consume a slot since they never actually run.
+        let mut ready: Vec<String> = Vec::new();
+        let mut capacity = max_concurrency.saturating_sub(in_flight.len());
+        remaining.retain(|id| {
+            let spec = &by_id[id];
+            let blocked_by_failure = abort_requested || spec.depends_on.iter().any(|d| failed_or_skipped.contains(d));
+            let all_deps_done = spec.depends_on.iter().all(|d| completed.contains(d));
+
+            if blocked_by_failure {
+                ready.push(id.clone());
+                false
+            } else if all_deps_done && capacity > 0 {
+                capacity -= 1;
+                ready.push(id.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        for id in ready {
+            let spec = by_id[&id].clone();
+            if failed_or_skipped.contains(&id) {
+                continue;
+            }
+            if in_flight.contains(&id) {
+                on_transition(&id, StepState::AlreadyRunning);
+                continue;
+            }
+
+            // Dependencies that were skipped/failed (or a plan-level abort)
+            // short-circuit this step without spending an attempt.
+            let blocked = abort_requested || spec.depends_on.iter().any(|d| failed_or_skipped.contains(d));
+            if blocked {
+                failed_or_skipped.insert(id.clone());
+                on_transition(&id, StepState::Skipped);
+                let outcome = StepOutcome {
+                    id: id.clone(),
+                    state: StepState::Skipped,
+                    result: serde_json::json!({ "reason": "dependency did not complete" }),
+                    started_at: now_ms(),
+                    completed_at: now_ms(),
+                    attempts: 0,
+                    terminal: false,
+                };
+                on_outcome(&outcome);
+                outcomes.insert(id.clone(), outcome);
+                continue;
+            }
+
+            let dep_results: HashMap<String, serde_json::Value> = spec
+                .depends_on
+                .iter()
+                .filter_map(|d| outcomes.get(d).map(|o| (d.clone(), o.result.clone())))
+                .collect();
+
+            in_flight.insert(id.clone());
+            on_transition(&id, StepState::Running);
+
+            let execute = execute.clone();
+            join_set.spawn(async move {
+                let started_at = now_ms();
+                let mut attempts = 0u32;
+                loop {
+                    attempts += 1;
+                    match execute(spec.clone(), dep_results.clone()).await {
+                        Ok(value) => {
+                            return StepOutcome {
+                                id: spec.id.clone(),
+                                state: StepState::Completed,
+                                result: value.into(),
+                                started_at,
+                                completed_at: now_ms(),
+                                attempts,
+                                terminal: false,
+                            };
+                        }
+                        Err(e) => {
+                            let terminal = !e.is_retryable();
+                            if !terminal && attempts < retry_policy.max_attempts {
+                                let delay = retry_policy.delay_for_attempt(attempts);
+                                tokio::time::sleep(delay).await;
+                                continue;
+                            }
+                            return StepOutcome {
+                                id: spec.id.clone(),
+                                state: StepState::Failed,
+                                result: serde_json::json!({ "error": e }),
+                                started_at,
+                                completed_at: now_ms(),
+                                attempts,
+                                terminal,
+                            };
+                        }
+                    }
+                }
+            });
+        }
+
+        if let Some(joined) = join_set.join_next().await {
+            if let Ok(outcome) = joined {
+                in_flight.remove(&outcome.id);
+                match outcome.state {
+                    StepState::Completed => {
+                        completed.insert(outcome.id.clone());
+                    }
+                    StepState::Failed | StepState::Skipped => {
+                        if outcome.state == StepState::Failed && outcome.terminal && abort_on_terminal_failure {
+                            abort_requested = true;
+                        }
+                        failed_or_skipped.insert(outcome.id.clone());
+                    }
+                    _ => {}
+                }
+                on_transition(&outcome.id, outcome.state);
+                on_outcome(&outcome);
+                outcomes.insert(outcome.id.clone(), outcome);
+            }
+        }
+    }
+
+    steps.into_iter().filter_map(|s| outcomes.remove(&s.id)).collect()
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Regression test for `reject_cycles`: two steps in the same stage that
+    /// `needs` each other form a dependency cycle and must be rejected
+    /// rather than silently accepted (which would otherwise deadlock
+    /// `scheduler::run`, since neither step's dependencies would ever be
+    /// satisfied).
+    #[test]
+    fn parse_plan_rejects_a_needs_cycle() {
+        let err = parse_plan("alpha: do x -> a needs b AND alpha: do y -> b needs a", "alpha")
+            .expect_err("mutual needs should be rejected as a cycle");
+        assert!(err.contains("cycle"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn parse_plan_accepts_acyclic_needs() {
+        let steps = parse_plan("alpha: do x -> a THEN alpha: do y needs a", "alpha")
+            .expect("acyclic plan should parse");
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[1].depends_on, vec!["step0".to_string()]);
+    }
+}
@@ -0,0 +1,149 @@
+// Schema and canned statements for the embedded SQLite store owned by
+// `dbctx`. Kept as plain `&str` constants (rather than an ORM) so the
+// statements stay easy to read and diff alongside the migrations they
+// belong to.
+
+pub const MIGRATIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS goblins (
+    id TEXT PRIMARY KEY,
+    last_seen INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS history_entries (
+    goblin_id TEXT NOT NULL,
+    ts INTEGER NOT NULL,
+    message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_history_entries_goblin_ts ON history_entries(goblin_id, ts);
+
+CREATE TABLE IF NOT EXISTS tasks (
+    task_id TEXT PRIMARY KEY,
+    goblin TEXT NOT NULL,
+    task TEXT NOT NULL,
+    status TEXT NOT NULL,
+    provider TEXT,
+    model TEXT,
+    total_cost REAL NOT NULL DEFAULT 0,
+    started_at INTEGER,
+    completed_at INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS orchestration_plans (
+    id TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS plan_steps (
+    id TEXT NOT NULL,
+    plan_id TEXT NOT NULL,
+    goblin TEXT NOT NULL,
+    task TEXT NOT NULL,
+    status TEXT NOT NULL,
+    started_at INTEGER,
+    completed_at INTEGER,
+    result TEXT,
+    PRIMARY KEY (plan_id, id)
+);
+CREATE INDEX IF NOT EXISTS idx_plan_steps_plan_id ON plan_steps(plan_id);
+"#;
+
+/// `orchestration_plans`/`plan_steps` gained these columns after their first
+/// release (to support resuming a plan across a restart). SQLite has no
+/// `ADD COLUMN IF NOT EXISTS`, so `dbctx::DbCtx::open` runs each of these
+/// through `add_column_if_missing`, which tolerates the "duplicate column
+/// name" error a second run raises.
+pub const PLAN_COLUMNS: &[(&str, &str, &str)] = &[
+    ("orchestration_plans", "default_goblin", "TEXT"),
+    ("plan_steps", "name", "TEXT"),
+    ("plan_steps", "depends_on", "TEXT"),
+    ("plan_steps", "attempts", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+pub const INSERT_HISTORY: &str =
+    "INSERT INTO history_entries (goblin_id, ts, message) VALUES (?1, ?2, ?3)";
+pub const SELECT_HISTORY: &str =
+    "SELECT ts, message FROM history_entries WHERE goblin_id = ?1 ORDER BY ts DESC LIMIT ?2";
+
+/// Retention enforcement, run after every insert: keep only the newest
+/// `?2` rows per goblin (by `rowid`, since `ts` isn't guaranteed unique).
+pub const DELETE_HISTORY_OVER_MAX_ROWS: &str =
+    "DELETE FROM history_entries WHERE goblin_id = ?1 AND rowid NOT IN ( \
+        SELECT rowid FROM history_entries WHERE goblin_id = ?1 ORDER BY ts DESC LIMIT ?2)";
+/// Retention enforcement, run after every insert: drop rows older than `?2`.
+pub const DELETE_HISTORY_OLDER_THAN: &str =
+    "DELETE FROM history_entries WHERE goblin_id = ?1 AND ts < ?2";
+
+pub const UPSERT_GOBLIN_LAST_SEEN: &str =
+    "INSERT INTO goblins (id, last_seen) VALUES (?1, ?2) \
+     ON CONFLICT(id) DO UPDATE SET last_seen = excluded.last_seen";
+pub const SELECT_GOBLIN_LAST_SEEN: &str = "SELECT last_seen FROM goblins WHERE id = ?1";
+
+pub const UPSERT_TASK: &str =
+    "INSERT INTO tasks (task_id, goblin, task, status, provider, model, total_cost, started_at, completed_at) \
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) \
+     ON CONFLICT(task_id) DO UPDATE SET \
+        status = excluded.status, \
+        provider = excluded.provider, \
+        model = excluded.model, \
+        total_cost = excluded.total_cost, \
+        completed_at = excluded.completed_at";
+
+pub const SELECT_COST_BY_PROVIDER: &str =
+    "SELECT provider, SUM(total_cost) FROM tasks \
+     WHERE provider IS NOT NULL \
+       AND (?1 IS NULL OR started_at >= ?1) \
+       AND (?2 IS NULL OR started_at <= ?2) \
+     GROUP BY provider";
+
+/// Backs `get_cost_summary`: an optional `goblin` filter alongside the same
+/// `[since, until]` time window used by `SELECT_COST_BY_PROVIDER`.
+pub const SELECT_COST_SUMMARY_TOTAL: &str =
+    "SELECT COALESCE(SUM(total_cost), 0) FROM tasks \
+     WHERE (?1 IS NULL OR goblin = ?1) \
+       AND (?2 IS NULL OR started_at >= ?2) \
+       AND (?3 IS NULL OR started_at <= ?3)";
+pub const SELECT_COST_SUMMARY_BY_PROVIDER: &str =
+    "SELECT provider, SUM(total_cost) FROM tasks \
+     WHERE provider IS NOT NULL \
+       AND (?1 IS NULL OR goblin = ?1) \
+       AND (?2 IS NULL OR started_at >= ?2) \
+       AND (?3 IS NULL OR started_at <= ?3) \
+     GROUP BY provider";
+pub const SELECT_COST_SUMMARY_BY_MODEL: &str =
+    "SELECT model, SUM(total_cost) FROM tasks \
+     WHERE model IS NOT NULL \
+       AND (?1 IS NULL OR goblin = ?1) \
+       AND (?2 IS NULL OR started_at >= ?2) \
+       AND (?3 IS NULL OR started_at <= ?3) \
+     GROUP BY model";
+
+pub const UPSERT_PLAN: &str =
+    "INSERT INTO orchestration_plans (id, description, status, created_at, default_goblin) VALUES (?1, ?2, ?3, ?4, ?5) \
+     ON CONFLICT(id) DO UPDATE SET status = excluded.status";
+// `id` ("step0", "step1", ...) is only unique within a single parse of one
+// plan's orchestration text - a second plan's steps restart from "step0" -
+// so the primary key (and this upsert's conflict target) has to be the
+// `(plan_id, id)` pair, not `id` alone, or a second plan's "step0" would
+// silently overwrite the first plan's.
+pub const UPSERT_PLAN_STEP: &str =
+    "INSERT INTO plan_steps (id, plan_id, name, goblin, task, depends_on, status, started_at, completed_at, result, attempts) \
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11) \
+     ON CONFLICT(plan_id, id) DO UPDATE SET \
+        status = excluded.status, \
+        started_at = excluded.started_at, \
+        completed_at = excluded.completed_at, \
+        result = excluded.result, \
+        attempts = excluded.attempts";
+
+pub const SELECT_PLAN: &str =
+    "SELECT id, description, status, created_at, default_goblin FROM orchestration_plans WHERE id = ?1";
+pub const SELECT_PLANS: &str =
+    "SELECT id, description, status, created_at, default_goblin FROM orchestration_plans ORDER BY created_at DESC LIMIT ?1";
+pub const SELECT_INCOMPLETE_PLANS: &str =
+    "SELECT id, description, status, created_at, default_goblin FROM orchestration_plans \
+     WHERE status NOT IN ('completed', 'failed') ORDER BY created_at ASC";
+pub const SELECT_PLAN_STEPS: &str =
+    "SELECT id, name, goblin, task, depends_on, status, started_at, completed_at, result, attempts \
+     FROM plan_steps WHERE plan_id = ?1";
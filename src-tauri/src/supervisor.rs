@@ -0,0 +1,31 @@
+// Lifecycle bookkeeping for the supervised goblin-runtime child process.
+// `goblin_runtime::supervise` drains its exit status and restarts it with
+// backoff; this module holds the small bits of state and policy that
+// decision needs.
+
+use serde::{Deserialize, Serialize};
+
+/// Where the runtime is in its lifecycle, so the frontend can tell a clean
+/// stop apart from a crash loop instead of just seeing `running: false`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentState {
+    Starting,
+    Running,
+    Crashed,
+    Restarting,
+    Stopped,
+}
+
+/// Restart attempts after an unexpected exit before we give up and leave
+/// the runtime `Crashed`.
+pub const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+const RESTART_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Exponential backoff delay before restart attempt `attempt` (1-indexed),
+/// capped so a long crash loop doesn't wait longer than ~a minute between
+/// tries.
+pub fn restart_delay(attempt: u32) -> std::time::Duration {
+    RESTART_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1).min(6))
+}
@@ -0,0 +1,19 @@
+// Token counting for cost estimation. This was meant to wrap a real
+// byte-level BPE tokenizer per provider (cl100k_base for OpenAI, Anthropic's
+// own encoding), but an accurate merge-rank table for either one isn't
+// something that can be hand-authored - a real cl100k_base table has on the
+// order of 100k ranked merges, sourced from tiktoken's published data, not
+// written out by hand. Shipping a hand-rolled table of a few dozen toy
+// ASCII bigrams would look like real tokenization while silently driving
+// budget admission and cost reporting off noise, and with OpenAI and
+// Anthropic mapped to the same toy table it wouldn't even be per-provider.
+// Until real merge tables are vendored in, this is honestly just the
+// char/4 heuristic for every provider.
+use crate::cost_estimator;
+
+/// Estimate how many tokens `text` would cost for `provider`/`model`.
+/// Currently the same char/4 estimate regardless of provider - see the
+/// module comment for why this isn't (yet) a real per-provider tokenizer.
+pub fn count_tokens(_provider: &str, _model: Option<&str>, text: &str) -> usize {
+    cost_estimator::estimate_tokens_from_text(text)
+}
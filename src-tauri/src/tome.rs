@@ -0,0 +1,290 @@
+// A goblin's scripted "tome" (see `config::load_goblin_tome`): a sequenced
+// program of provider calls, each named so a later step can branch on or
+// splice in an earlier one's result, interpreted step-by-step through the
+// `Runtime` trait's host capabilities (`call_step`, `estimate_cost`/
+// `charge_cost`, `send_event`, `add_history_entry`) instead of being
+// compiled into `scheduler`'s concurrent DAG - the DAG has no construct for
+// "do X, then decide what to do next based on what X returned", which is
+// the whole point of a tome. `run_tome_impl` parses a tome once into a
+// `TomeProgram` and drives it with `run_tome_program`.
+//
+// Grammar, a small line-oriented one like `scheduler::parse_plan`'s rather
+// than a general expression language (tomes are short hand-written scripts
+// in `goblins.yaml`, not an embedded programming language):
+//
+//   program := step (THEN step)*
+//   step    := call | branch
+//   call    := <goblin: task, same as scheduler::parse_step_token> ("-> name")?
+//   branch  := "IF" "[" name "]" "CONTAINS" "\"text\"" "CALL" call ("ELSE" "CALL" call)? "ENDIF"
+//
+// A branch's condition is always whether an earlier named step's result
+// contains a literal substring, and each arm is a single call - no nested
+// branches - which covers "do one of two things based on what happened"
+// without needing a real parser for a real expression language.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::cost_tracker::CostTracker;
+use crate::error::{GoblinError, GoblinResult};
+use crate::progress::{Progress, ProgressRegistry};
+use crate::runtime_trait::Runtime;
+use crate::scheduler;
+
+/// One step of a tome program, already parsed.
+pub enum TomeStep {
+    /// Call `goblin` with `task` (after splicing in `[name]` references to
+    /// earlier steps), recording the result under `name` - either the
+    /// explicit `-> name`, or `step<N>`, where N counts only the unnamed
+    /// calls, in parse order.
+    Call { name: String, goblin: String, task: String },
+    /// Branch on whether the named step's result contains `needle`: run
+    /// `then_branch` if it does, `else_branch` (possibly empty) otherwise.
+    Branch { check: String, needle: String, then_branch: Vec<TomeStep>, else_branch: Vec<TomeStep> },
+}
+
+pub type TomeProgram = Vec<TomeStep>;
+
+fn unquote(token: &str) -> Result<String, String> {
+    let trimmed = token.trim();
+    if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        Ok(trimmed[1..trimmed.len() - 1].to_string())
+    } else {
+        Err(format!("expected a quoted \"string\", found: {}", token))
+    }
+}
+
+/// Split `text` into words, keeping any `"quoted string"` (which may
+/// contain spaces) as a single token.
+fn tokenize_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut current = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '"' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            let mut quoted = String::from("\"");
+            chars.next();
+            while let Some(c2) = chars.next() {
+                quoted.push(c2);
+                if c2 == '"' {
+                    break;
+                }
+            }
+            words.push(quoted);
+        } else if c.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            chars.next();
+        } else {
+            current.push(c);
+            chars.next();
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Split `words` on top-level occurrences of `keyword` - `IF`/`ENDIF` pairs
+/// nest so a keyword inside one doesn't split the outer step. Tomes only
+/// ever need this one level deep (branch arms don't nest further), but
+/// tracking depth costs nothing and keeps this correct if that changes.
+fn split_at_keyword(words: &[String], keyword: &str) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0i32;
+    for word in words {
+        if word == "IF" {
+            depth += 1;
+        } else if word == "ENDIF" {
+            depth -= 1;
+        }
+        if depth == 0 && word == keyword {
+            groups.push(std::mem::take(&mut current));
+        } else {
+            current.push(word.clone());
+        }
+    }
+    groups.push(current);
+    groups
+}
+
+fn strip_prefix_word(words: &[String], expected: &str) -> Result<Vec<String>, String> {
+    match words.first() {
+        Some(w) if w == expected => Ok(words[1..].to_vec()),
+        other => Err(format!("expected `{}`, found: {:?}", expected, other)),
+    }
+}
+
+fn strip_suffix_word(words: &[String], expected: &str) -> Result<Vec<String>, String> {
+    match words.last() {
+        Some(w) if w == expected => Ok(words[..words.len() - 1].to_vec()),
+        other => Err(format!("expected trailing `{}`, found: {:?}", expected, other)),
+    }
+}
+
+fn parse_call_step(text: &str, default_goblin: &str, next_id: &mut usize) -> Result<TomeStep, String> {
+    let (goblin, task, explicit_name, _needs, _auto_route) = scheduler::parse_step_token(text, default_goblin);
+    if task.trim().is_empty() {
+        return Err(format!("tome step has no task text: {}", text));
+    }
+    let name = explicit_name.unwrap_or_else(|| {
+        let id = format!("step{}", next_id);
+        *next_id += 1;
+        id
+    });
+    Ok(TomeStep::Call { name, goblin, task })
+}
+
+fn parse_branch_step(words: &[String], default_goblin: &str, next_id: &mut usize) -> Result<TomeStep, String> {
+    // words[0] is the "IF" the caller dispatched on; it is checked again here.
+    if words.len() < 5 || words[0] != "IF" || words[2] != "CONTAINS" || words[4] != "CALL" {
+        return Err(format!("malformed IF step: {}", words.join(" ")));
+    }
+    let check = words[1].trim_start_matches('[').trim_end_matches(']').to_string();
+    if check.is_empty() || check == words[1] {
+        return Err(format!("expected `[name]` after IF, found: {}", words[1]));
+    }
+    let needle = unquote(&words[3])?;
+
+    let groups = split_at_keyword(&words[5..], "ELSE");
+    let (then_words, else_call_words) = match groups.as_slice() {
+        [then_part] => (strip_suffix_word(then_part, "ENDIF")?, None),
+        [then_part, else_part] => {
+            let else_part = strip_prefix_word(else_part, "CALL")?;
+            (then_part.clone(), Some(strip_suffix_word(&else_part, "ENDIF")?))
+        }
+        _ => return Err(format!("IF step has more than one ELSE: {}", words.join(" "))),
+    };
+
+    let then_branch = vec![parse_call_step(&then_words.join(" "), default_goblin, next_id)?];
+    let else_branch = match else_call_words {
+        Some(words) => vec![parse_call_step(&words.join(" "), default_goblin, next_id)?],
+        None => Vec::new(),
+    };
+
+    Ok(TomeStep::Branch { check, needle, then_branch, else_branch })
+}
+
+fn parse_step(words: &[String], default_goblin: &str, next_id: &mut usize) -> Result<TomeStep, String> {
+    if words.first().map(|w| w.as_str()) == Some("IF") {
+        parse_branch_step(words, default_goblin, next_id)
+    } else {
+        parse_call_step(&words.join(" "), default_goblin, next_id)
+    }
+}
+
+/// Parse a tome script (see the module comment for the grammar).
+pub fn parse_tome(text: &str, default_goblin: &str) -> Result<TomeProgram, String> {
+    let words = tokenize_words(text.trim());
+    if words.is_empty() {
+        return Err("tome has no steps".to_string());
+    }
+
+    let mut next_id = 0usize;
+    split_at_keyword(&words, "THEN")
+        .into_iter()
+        .map(|step_words| parse_step(&step_words, default_goblin, &mut next_id))
+        .collect()
+}
+
+/// Count how many top-level steps (branches count as one) `program` has,
+/// for the progress bar's `total_steps`.
+fn step_count(program: &TomeProgram) -> usize {
+    program.len()
+}
+
+async fn call_and_record(
+    app: &tauri::AppHandle,
+    runtime: &dyn Runtime,
+    goblin: &str,
+    task: &str,
+    tracker: &CostTracker,
+    results: &HashMap<String, JsonValue>,
+) -> GoblinResult<JsonValue> {
+    let spliced = super::splice_dependency_results(task, results);
+    let (cost, tokens) = runtime.estimate_cost(goblin, &spliced);
+    if !runtime.charge_cost(tracker, cost, tokens) {
+        return Err(GoblinError::Io(format!("tome step for '{}' aborted: over budget (cost {:.6})", goblin, cost)));
+    }
+
+    let system_prompt = super::get_system_prompt(&spliced);
+    let response = runtime.call_step(goblin, &spliced, system_prompt, None).await?;
+
+    runtime.add_history_entry(goblin, &super::dependency_output_text(&response)).await;
+    runtime.send_event(app, "task-stream", json!({
+        "goblin": goblin,
+        "task": spliced,
+        "result": response.clone(),
+        "cost": cost
+    })).await;
+
+    Ok(response)
+}
+
+/// Run `steps` in order against `results`' accumulated state, recursing into
+/// whichever arm a `Branch` step picks and publishing a progress update
+/// after every executed `Call` (a taken branch's arm counts as one step,
+/// same as a plain call). Boxed because an `async fn` can't recurse
+/// directly.
+fn run_steps<'a>(
+    app: &'a tauri::AppHandle,
+    runtime: &'a dyn Runtime,
+    steps: &'a [TomeStep],
+    tracker: &'a CostTracker,
+    results: &'a mut HashMap<String, JsonValue>,
+    tx: &'a tokio::sync::watch::Sender<Progress>,
+    done: &'a mut usize,
+    total_steps: usize,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = GoblinResult<()>> + Send + 'a>> {
+    Box::pin(async move {
+        for step in steps {
+            match step {
+                TomeStep::Call { name, goblin, task } => {
+                    let result = call_and_record(app, runtime, goblin, task, tracker, results).await?;
+                    results.insert(name.clone(), result);
+                    *done += 1;
+                    let _ = tx.send(Progress { step: *done, total_steps, bytes_or_tokens_done: 0, message: format!("ran {}", name) });
+                }
+                TomeStep::Branch { check, needle, then_branch, else_branch } => {
+                    let matched = results
+                        .get(check)
+                        .map(|result| super::dependency_output_text(result).contains(needle.as_str()))
+                        .unwrap_or(false);
+                    let arm = if matched { then_branch } else { else_branch };
+                    run_steps(app, runtime, arm, tracker, results, tx, done, total_steps).await?;
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Drive `program` to completion against `tracker`'s budget, reporting
+/// per-step progress under `progress_id` and returning the result of every
+/// step that ran, keyed by name. The map keeps no run order, and a call in
+/// a branch arm that was not taken has no entry.
+pub async fn run_tome_program(
+    app: &tauri::AppHandle,
+    runtime: &dyn Runtime,
+    program: &TomeProgram,
+    tracker: &CostTracker,
+    progress: &ProgressRegistry,
+    progress_id: &str,
+) -> GoblinResult<HashMap<String, JsonValue>> {
+    let total_steps = step_count(program);
+    let tx = progress.register(app.clone(), progress_id, Progress::new(total_steps)).await;
+
+    let mut results = HashMap::new();
+    let mut done = 0usize;
+    let outcome = run_steps(app, runtime, program, tracker, &mut results, &tx, &mut done, total_steps).await;
+
+    progress.unregister(progress_id).await;
+    outcome.map(|()| results)
+}
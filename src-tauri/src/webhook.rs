@@ -0,0 +1,177 @@
+// HTTP entry point for kicking off orchestration plans from outside the
+// desktop app. Mirrors `ipc.rs`: a thin transport in front of the same
+// `goblin_runtime::*_impl` functions the Tauri commands call, just reached
+// over HTTP instead of IPC. `spawn_webhook_server` is started once from
+// `main.rs`'s `setup()`, alongside the runtime's other background tasks.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::goblin_runtime;
+use crate::GoblinRuntimeManager;
+use tauri::Manager;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default port for the webhook server; overridden by `GOBLIN_WEBHOOK_PORT`.
+const DEFAULT_WEBHOOK_PORT: u16 = 4317;
+
+struct WebhookState {
+    app: tauri::AppHandle,
+    /// Pre-shared HMAC keys, name -> secret, so a verified request can be
+    /// attributed to whichever caller's key matched.
+    keys: HashMap<String, String>,
+}
+
+/// Load `GOBLIN_WEBHOOK_KEYS` as comma-separated `name:secret` pairs (e.g.
+/// `ci:abc123,partner-x:def456`). Naive on purpose, matching this repo's
+/// other env-driven config - a real secrets manager can replace this if the
+/// caller list grows past a handful of entries.
+fn load_webhook_keys() -> HashMap<String, String> {
+    std::env::var("GOBLIN_WEBHOOK_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, ':');
+            let name = parts.next()?.trim();
+            let secret = parts.next()?.trim();
+            if name.is_empty() || secret.is_empty() {
+                return None;
+            }
+            Some((name.to_string(), secret.to_string()))
+        })
+        .collect()
+}
+
+/// Check `signature_hex` (the `X-Signature` header) against every configured
+/// key's HMAC-SHA256 over the raw request body, returning the name of
+/// whichever key matched. `Mac::verify_slice` compares in constant time, so
+/// a caller can't use response timing to guess a key byte-by-byte.
+fn verify_signature(keys: &HashMap<String, String>, body: &[u8], signature_hex: &str) -> Option<String> {
+    let provided = hex::decode(signature_hex.trim()).ok()?;
+    keys.iter().find_map(|(name, secret)| {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts a key of any length");
+        mac.update(body);
+        mac.verify_slice(&provided).ok().map(|_| name.clone())
+    })
+}
+
+#[derive(Deserialize)]
+struct OrchestrateRequest {
+    text: String,
+    default_goblin: Option<String>,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<usize>,
+    abort_on_failure: Option<bool>,
+    max_concurrency: Option<usize>,
+}
+
+/// `POST /orchestrate` - verify `X-Signature`, parse the plan, then run it
+/// and stream each step's result back as newline-delimited JSON as soon as
+/// it transitions to `completed`/`failed`, instead of blocking until the
+/// whole plan finishes.
+async fn orchestrate(State(state): State<Arc<WebhookState>>, headers: HeaderMap, body: Bytes) -> Response {
+    let signature = match headers.get("X-Signature").and_then(|v| v.to_str().ok()) {
+        Some(s) => s,
+        None => return (StatusCode::UNAUTHORIZED, "missing X-Signature header").into_response(),
+    };
+
+    let caller = match verify_signature(&state.keys, &body, signature) {
+        Some(name) => name,
+        None => return (StatusCode::UNAUTHORIZED, "signature verification failed").into_response(),
+    };
+
+    let request: OrchestrateRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid request body: {}", e)).into_response(),
+    };
+
+    println!("Webhook /orchestrate call from '{}'", caller);
+
+    // Validate the plan up front so a malformed grammar is rejected before
+    // we commit to a streaming response.
+    if let Err(e) = goblin_runtime::parse_orchestration_impl(&request.text, request.default_goblin.clone()).await {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel::<goblin_runtime::OrchestrationStepResult>();
+    let app = state.app.clone();
+    let progress = app.state::<Arc<GoblinRuntimeManager>>().progress.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = goblin_runtime::execute_orchestration_streamed(
+            app,
+            &request.text,
+            request.default_goblin,
+            request.budget_usd,
+            request.budget_tokens,
+            request.abort_on_failure,
+            request.max_concurrency,
+            progress,
+            move |step| {
+                let _ = tx.send(step);
+            },
+        )
+        .await;
+        if let Err(e) = outcome {
+            println!("Webhook orchestration failed: {}", e);
+        }
+    });
+
+    let stream = UnboundedReceiverStream::new(rx).map(|step| {
+        let mut line = serde_json::to_vec(&step).unwrap_or_default();
+        line.push(b'\n');
+        Ok::<_, std::convert::Infallible>(Bytes::from(line))
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .unwrap()
+        .into_response()
+}
+
+/// Start the webhook HTTP server in the background. A no-op if no keys are
+/// configured, so the server doesn't bind a port (and silently accept
+/// unauthenticatable requests) in setups that never opted into it.
+pub fn spawn_webhook_server(app: tauri::AppHandle) {
+    let keys = load_webhook_keys();
+    if keys.is_empty() {
+        println!("No GOBLIN_WEBHOOK_KEYS configured, webhook server not started");
+        return;
+    }
+
+    let port: u16 = std::env::var("GOBLIN_WEBHOOK_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_WEBHOOK_PORT);
+    let state = Arc::new(WebhookState { app, keys });
+
+    tauri::async_runtime::spawn(async move {
+        let router = Router::new().route("/orchestrate", post(orchestrate)).with_state(state);
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => {
+                println!("Webhook server listening on {}", addr);
+                if let Err(e) = axum::serve(listener, router).await {
+                    println!("Webhook server error: {}", e);
+                }
+            }
+            Err(e) => println!("Failed to bind webhook server on {}: {}", addr, e),
+        }
+    });
+}